@@ -0,0 +1,103 @@
+use crate::error::ConversionError;
+use crate::openssl::InputFormat;
+use glob::MatchOptions;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Check whether an input is a glob pattern rather than a plain path
+pub fn is_pattern(input: &str) -> bool {
+    input.contains(['*', '?', '['])
+}
+
+/// Expand `--pfx` inputs into the files to convert
+///
+/// Files are taken as given. Directories contribute the files whose content is
+/// PKCS#12, whatever their name, descending into subdirectories when
+/// `recursive` is set. Glob patterns (`*`, `?`, `**`, `[a-z]`, `[!a-z]`, with
+/// `[*]` matching a literal `*`) are matched against the file system; like a
+/// shell, a pattern without matches is kept as is, so it fails as a missing
+/// file. Each file is listed once, in the order found.
+pub fn expand_inputs(inputs: &[String], recursive: bool) -> Result<Vec<PathBuf>, ConversionError> {
+    let mut files = Vec::new();
+    for input in inputs {
+        let mut paths = if is_pattern(input) {
+            glob(input)
+        } else {
+            Vec::new()
+        };
+        if paths.is_empty() {
+            paths.push(PathBuf::from(input));
+        }
+
+        for path in paths {
+            if path.is_dir() {
+                collect_dir(&path, recursive, &mut files)?;
+            } else {
+                files.push(path);
+            }
+        }
+    }
+
+    let mut unique = Vec::with_capacity(files.len());
+    for file in files {
+        if !unique.contains(&file) {
+            unique.push(file);
+        }
+    }
+    Ok(unique)
+}
+
+/// Add the PFX files of a directory, sorted by name; hidden entries are skipped
+fn collect_dir(
+    dir: &Path,
+    recursive: bool,
+    files: &mut Vec<PathBuf>,
+) -> Result<(), ConversionError> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)
+        .and_then(|entries| entries.map(|entry| entry.map(|e| e.path())).collect())
+        .map_err(|e| ConversionError::FileRead(dir.display().to_string(), e))?;
+    paths.sort();
+
+    for path in paths {
+        if path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+        {
+            continue;
+        }
+        if path.is_dir() {
+            if recursive {
+                collect_dir(&path, recursive, files)?;
+            }
+        } else if path.is_file() && is_pfx_file(&path) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Check whether a directory entry holds PKCS#12 data, DER or base64
+///
+/// Files that cannot be read are kept, so their error is reported.
+fn is_pfx_file(path: &Path) -> bool {
+    fs::read(path).map_or(true, |data| {
+        matches!(
+            InputFormat::detect(&data),
+            InputFormat::Pkcs12Der | InputFormat::Pkcs12Base64
+        )
+    })
+}
+
+/// Find the existing paths matching a glob pattern, sorted by name
+///
+/// Like a shell, wildcards do not match a leading dot. An invalid pattern
+/// matches nothing.
+fn glob(pattern: &str) -> Vec<PathBuf> {
+    let options = MatchOptions {
+        require_literal_leading_dot: true,
+        ..MatchOptions::new()
+    };
+    glob::glob_with(pattern, options)
+        .map(|paths| paths.filter_map(Result::ok).collect())
+        .unwrap_or_default()
+}
@@ -0,0 +1,125 @@
+use crate::cli::password::{can_prompt, first_line, prompt_new_password};
+use crate::error::ConversionError;
+use crate::openssl::{DEFAULT_PBKDF2_ITERATIONS, DEFAULT_SCRYPT_COST, KeyEncryption};
+use clap::ValueEnum;
+use std::env;
+use std::fs;
+
+/// Key derivation functions offered for encrypted private key output
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum KeyKdf {
+    /// PBKDF2 with HMAC-SHA256
+    #[default]
+    Pbkdf2,
+    /// scrypt (memory-hard)
+    Scrypt,
+}
+
+/// Options for encrypting the written private key
+///
+/// The passphrase is deliberately not accepted on the command line; it comes
+/// from an environment variable, a file, or an interactive prompt.
+#[derive(clap::Args, Debug, Default, Clone)]
+pub struct KeyEncryptionArgs {
+    /// Encrypt the private key (standalone and combined) as PKCS#8
+    #[arg(long, help = "Encrypt the private key output as PKCS#8 (AES-256-CBC)")]
+    pub encrypt_key: bool,
+
+    /// Name of an environment variable holding the key passphrase
+    #[arg(
+        long,
+        value_name = "NAME",
+        requires = "encrypt_key",
+        conflicts_with = "key_passphrase_file",
+        help = "Read the private key passphrase from the named environment variable"
+    )]
+    pub key_passphrase_env: Option<String>,
+
+    /// Path to a file whose first line is the key passphrase
+    #[arg(
+        long,
+        value_name = "PATH",
+        requires = "encrypt_key",
+        help = "Read the private key passphrase from the first line of a file"
+    )]
+    pub key_passphrase_file: Option<String>,
+
+    /// Key derivation function for the key encryption
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = KeyKdf::Pbkdf2,
+        requires = "encrypt_key",
+        help = "Key derivation function for the encrypted private key"
+    )]
+    pub key_kdf: KeyKdf,
+
+    /// PBKDF2 iteration count; scrypt always uses the highest cost OpenSSL can read
+    #[arg(
+        long,
+        value_name = "N",
+        requires = "encrypt_key",
+        help = "PBKDF2 iteration count (default 600000)"
+    )]
+    pub key_iterations: Option<u32>,
+}
+
+impl KeyEncryptionArgs {
+    /// Build the key encryption settings, reading or prompting for the passphrase
+    ///
+    /// Returns `None` when key encryption was not requested.
+    pub fn resolve(&self) -> Result<Option<KeyEncryption>, ConversionError> {
+        if !self.encrypt_key {
+            return Ok(None);
+        }
+
+        if self.key_kdf == KeyKdf::Scrypt && self.key_iterations.is_some() {
+            return Err(ConversionError::InvalidOption(format!(
+                "--key-iterations only applies to PBKDF2; scrypt always uses N={DEFAULT_SCRYPT_COST}, \
+                 the highest cost OpenSSL can decrypt"
+            )));
+        }
+
+        let passphrase = self.passphrase()?;
+        if passphrase.is_empty() {
+            return Err(ConversionError::PasswordInput(
+                "The private key passphrase must not be empty".to_string(),
+            ));
+        }
+
+        Ok(Some(match self.key_kdf {
+            KeyKdf::Pbkdf2 => KeyEncryption::pbkdf2(
+                passphrase,
+                self.key_iterations.unwrap_or(DEFAULT_PBKDF2_ITERATIONS),
+            ),
+            KeyKdf::Scrypt => KeyEncryption::scrypt(passphrase, DEFAULT_SCRYPT_COST),
+        }))
+    }
+
+    /// Read the passphrase from its source, prompting when none was given
+    fn passphrase(&self) -> Result<String, ConversionError> {
+        if let Some(ref name) = self.key_passphrase_env {
+            return env::var(name).map_err(|e| {
+                ConversionError::PasswordInput(format!(
+                    "Cannot read environment variable '{name}': {e}"
+                ))
+            });
+        }
+
+        if let Some(ref path) = self.key_passphrase_file {
+            let content =
+                fs::read_to_string(path).map_err(|e| ConversionError::FileRead(path.clone(), e))?;
+            return Ok(first_line(&content));
+        }
+
+        if !can_prompt() {
+            return Err(ConversionError::PasswordInput(
+                "--encrypt-key needs a passphrase: use --key-passphrase-env or \
+                 --key-passphrase-file when no terminal is available"
+                    .to_string(),
+            ));
+        }
+
+        prompt_new_password("Passphrase for the private key: ")
+    }
+}
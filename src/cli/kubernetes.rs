@@ -0,0 +1,49 @@
+use crate::kubernetes::{ManifestFormat, parse_label};
+
+/// Options for rendering a Kubernetes TLS Secret manifest
+#[derive(clap::Args, Debug, Default, Clone)]
+pub struct KubernetesArgs {
+    /// Name of the `kubernetes.io/tls` Secret to render
+    #[arg(
+        long = "k8s-secret",
+        value_name = "NAME",
+        help = "Also write a kubernetes.io/tls Secret manifest with this name"
+    )]
+    pub secret_name: Option<String>,
+
+    /// Namespace of the Secret
+    #[arg(
+        long = "k8s-namespace",
+        value_name = "NAMESPACE",
+        requires = "secret_name",
+        help = "Namespace of the Kubernetes Secret"
+    )]
+    pub namespace: Option<String>,
+
+    /// Labels of the Secret, as KEY=VALUE
+    #[arg(
+        long = "k8s-label",
+        value_name = "KEY=VALUE",
+        value_parser = parse_label,
+        requires = "secret_name",
+        help = "Label for the Kubernetes Secret (repeatable)"
+    )]
+    pub labels: Vec<(String, String)>,
+
+    /// Manifest format
+    #[arg(
+        long = "k8s-format",
+        value_enum,
+        default_value_t = ManifestFormat::Yaml,
+        requires = "secret_name",
+        help = "Format of the Kubernetes Secret manifest"
+    )]
+    pub format: ManifestFormat,
+}
+
+impl KubernetesArgs {
+    /// Get the manifest filename for a Secret
+    pub fn manifest_filename(&self, secret_name: &str) -> String {
+        format!("{secret_name}-secret.{}", self.format.extension())
+    }
+}
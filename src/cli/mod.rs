@@ -1,24 +1,70 @@
+mod inputs;
+mod key_encryption;
+mod kubernetes;
+mod password;
+mod permissions;
+mod values;
+mod verify;
+
+pub use inputs::{expand_inputs, is_pattern};
+pub use key_encryption::{KeyEncryptionArgs, KeyKdf};
+pub use kubernetes::KubernetesArgs;
+pub use password::{PasswordArgs, can_prompt, prompt_new_password, prompt_password};
+pub use permissions::PermissionArgs;
+pub use verify::VerifyArgs;
+
 use crate::error::ConversionError;
-use clap::Parser;
-use std::path::Path;
+use crate::kubernetes::validate_name;
+use crate::openssl::{ExpiryThresholds, KeyFormat, NameFormat, OutputEncoding, TimeZone};
+use crate::output::OutputFormat;
+use clap::{Parser, Subcommand, ValueEnum};
+use std::path::{Path, PathBuf};
 
-#[derive(Parser, Debug)]
+#[derive(Parser, Debug, Clone)]
 #[command(
     name = "forge",
     author = "Nick Hudson <nick.hudson@gmail.com>",
     version = "0.1.1",
     about = "Convert PFX/P12 certificate files to PEM format",
     long_about = "A Rust-based tool for converting PFX (PKCS#12) certificate files to PEM format. \
-                  Supports password-protected files, certificate chains, and various output options."
+                  Supports password-protected files, certificate chains, and various output options.",
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
 )]
 pub struct Args {
-    /// Path to the PFX/P12 file
-    #[arg(long, required = true, help = "Path to the PFX/P12 certificate file")]
-    pub pfx: String,
+    /// Optional subcommand (defaults to PFX to PEM conversion)
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// PFX/P12 files, directories or glob patterns, or `-` to read from stdin
+    #[arg(
+        long,
+        required = true,
+        num_args = 1..,
+        value_name = "PATH",
+        help = "PFX/P12 file(s), directories or glob patterns to convert ('-' reads DER from stdin)"
+    )]
+    pub pfx: Vec<String>,
+
+    /// Search directories given to --pfx recursively
+    #[arg(
+        long,
+        help = "Also convert PFX/P12 files in subdirectories of directories given to --pfx"
+    )]
+    pub recursive: bool,
+
+    /// How the output subdirectory of each input is named in batch mode
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = BatchDirName::File,
+        help = "Name each batch output subdirectory after the input file or the certificate's common name"
+    )]
+    pub dir_name: BatchDirName,
 
     /// Password for the PFX/P12 file
-    #[arg(long, help = "Password for the PFX file (if password-protected)")]
-    pub password: Option<String>,
+    #[command(flatten)]
+    pub password: PasswordArgs,
 
     /// Output directory for PEM files (defaults to current directory)
     #[arg(long, help = "Output directory for generated PEM files")]
@@ -47,15 +93,408 @@ pub struct Args {
     #[arg(long, help = "Extract and save the complete certificate chain")]
     pub chain: bool,
 
+    /// Leave the self-signed root certificate out of chain output
+    #[arg(
+        long,
+        help = "Omit the self-signed root from the chain (servers should not send it)"
+    )]
+    pub omit_root: bool,
+
+    /// Prove the key pair with a sign/verify round trip besides comparing public keys
+    #[arg(
+        long,
+        help = "Also check the key matches the certificate by signing and verifying test data"
+    )]
+    pub sign_check: bool,
+
+    /// Fail instead of warning when the lint finds errors
+    #[arg(
+        long,
+        help = "Fail the conversion when the security lint finds errors (weak keys, expired certificates...)"
+    )]
+    pub strict: bool,
+
+    /// Organization policy enforced on the parsed bundle
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Enforce a TOML or YAML policy file; any violated rule fails the conversion"
+    )]
+    pub policy: Option<PathBuf>,
+
+    /// Write PEM, binary DER, or both
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = OutputEncoding::Pem,
+        help = "Output encoding: pem, der (.der key, .cer certificates) or both"
+    )]
+    pub encoding: OutputEncoding,
+
+    /// Encoding of the private key output
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = KeyFormat::Pkcs8,
+        help = "Private key format: pkcs8, pkcs1 (RSA), sec1 (EC) or traditional (PKCS#1/SEC1 by key type)"
+    )]
+    pub key_format: KeyFormat,
+
+    /// Encryption of the private key output
+    #[command(flatten)]
+    pub key_encryption: KeyEncryptionArgs,
+
+    /// Kubernetes TLS Secret manifest output
+    #[command(flatten)]
+    pub kubernetes: KubernetesArgs,
+
+    /// Permissions and ownership of written files
+    #[command(flatten)]
+    pub permissions: PermissionArgs,
+
+    /// Verification of the certificate chain
+    #[command(flatten)]
+    pub verify: VerifyArgs,
+
+    /// Prometheus textfile-collector output
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Write Prometheus metrics for node_exporter's textfile collector to PATH (e.g. forge.prom)"
+    )]
+    pub metrics_file: Option<PathBuf>,
+
+    /// Replace existing output files
+    #[arg(
+        long,
+        overrides_with = "no_clobber",
+        help = "Overwrite existing output files"
+    )]
+    pub force: bool,
+
+    /// Refuse to replace existing output files (the default)
+    #[arg(
+        long,
+        overrides_with = "force",
+        help = "Never overwrite existing output files (default; cancels --force)"
+    )]
+    pub no_clobber: bool,
+
+    /// Load OpenSSL's legacy provider for RC2/RC4/DES encrypted PFX files
+    #[arg(
+        long,
+        help = "Allow legacy PFX encryption (RC2-40, RC4, DES) via OpenSSL's legacy provider"
+    )]
+    pub legacy: bool,
+
+    /// Write the selected PEM blocks to stdout instead of files
+    #[arg(
+        long,
+        value_enum,
+        value_name = "PART",
+        num_args = 0..=1,
+        default_missing_value = "combined",
+        conflicts_with_all = ["out", "combined", "key_file", "cert_file", "combined_file"],
+        help = "Write PEM to stdout instead of files (key, cert, chain, combined or secret)"
+    )]
+    pub stdout: Option<StdoutPart>,
+
+    /// Format of the final summary
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = OutputFormat::Text,
+        conflicts_with = "stdout",
+        help = "Summary format: text, or a json report on stdout"
+    )]
+    pub output_format: OutputFormat,
+
+    /// How subject and issuer names are written
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = NameFormat::Rfc4514,
+        help = "Distinguished name style: rfc4514 (CN=x,O=y,C=z) or oneline (C = z, O = y, CN = x)"
+    )]
+    pub name_format: NameFormat,
+
+    /// Time zone of displayed dates
+    #[arg(
+        long,
+        value_name = "ZONE",
+        default_value = "utc",
+        help = "Time zone of displayed dates: utc, local or an offset such as +02:00"
+    )]
+    pub time_zone: TimeZone,
+
+    /// Verbose output
+    #[arg(long, help = "Enable verbose output with detailed information")]
+    pub verbose: bool,
+}
+
+/// PEM blocks that can be streamed to stdout
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StdoutPart {
+    /// The private key only
+    Key,
+    /// The main certificate only
+    Cert,
+    /// The main certificate followed by the chain
+    Chain,
+    /// The private key followed by the certificate (and chain with --chain)
+    Combined,
+    /// A Kubernetes TLS Secret manifest (requires --k8s-secret)
+    Secret,
+}
+
+/// Naming of the output subdirectories of a batch conversion
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BatchDirName {
+    /// The input file name without its extension
+    #[default]
+    File,
+    /// The common name of the main certificate, falling back to the file name
+    Cn,
+}
+
+/// Subcommands other than the default PFX to PEM conversion
+#[derive(Subcommand, Debug, Clone)]
+pub enum Command {
+    /// Build a PFX/P12 file from PEM key, certificate and chain files
+    Pack(PackArgs),
+    /// Show full X.509 details of every certificate in a PFX/P12 file
+    Inspect(InspectArgs),
+    /// Check certificate expiry, exiting with monitoring plugin codes (0/1/2/3)
+    Check(CheckArgs),
+}
+
+/// Arguments for packing PEM files into a PFX/P12 file
+#[derive(clap::Args, Debug, Clone)]
+pub struct PackArgs {
+    /// Path to the PEM private key
+    #[arg(long, help = "Path to the PEM private key file")]
+    pub key: String,
+
+    /// Path to the PEM certificate (additional certificates are added to the chain)
+    #[arg(long, help = "Path to the PEM certificate file")]
+    pub cert: String,
+
+    /// Path to a PEM file with chain certificates
+    #[arg(long, help = "Path to a PEM file containing the certificate chain")]
+    pub chain: Option<String>,
+
+    /// Output path for the PFX file (defaults to certificate.pfx)
+    #[arg(long, help = "Output path for the generated PFX file")]
+    out: Option<String>,
+
+    /// Friendly name stored in the PFX file
+    #[arg(long, help = "Friendly name (alias) for the certificate and key")]
+    pub name: Option<String>,
+
+    /// Password used to encrypt the PFX file
+    #[command(flatten)]
+    pub password: PasswordArgs,
+
+    /// Replace existing output files
+    #[arg(
+        long,
+        overrides_with = "no_clobber",
+        help = "Overwrite existing output files"
+    )]
+    pub force: bool,
+
+    /// Refuse to replace existing output files (the default)
+    #[arg(
+        long,
+        overrides_with = "force",
+        help = "Never overwrite existing output files (default; cancels --force)"
+    )]
+    pub no_clobber: bool,
+
+    /// Verbose output
+    #[arg(long, help = "Enable verbose output with detailed information")]
+    pub verbose: bool,
+}
+
+/// Arguments for inspecting the certificates in a PFX/P12 file
+#[derive(clap::Args, Debug, Clone)]
+pub struct InspectArgs {
+    /// Path to the PFX/P12 file, or `-` to read from stdin
+    #[arg(
+        long,
+        help = "Path to the PFX/P12 certificate file ('-' reads DER from stdin)"
+    )]
+    pub pfx: String,
+
+    /// Password for the PFX/P12 file
+    #[command(flatten)]
+    pub password: PasswordArgs,
+
+    /// Load OpenSSL's legacy provider for RC2/RC4/DES encrypted PFX files
+    #[arg(
+        long,
+        help = "Allow legacy PFX encryption (RC2-40, RC4, DES) via OpenSSL's legacy provider"
+    )]
+    pub legacy: bool,
+
+    /// Format of the inspection output
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = OutputFormat::Text,
+        help = "Output format: text tables, or a json document on stdout"
+    )]
+    pub output_format: OutputFormat,
+
+    /// How subject and issuer names are written
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = NameFormat::Rfc4514,
+        help = "Distinguished name style: rfc4514 (CN=x,O=y,C=z) or oneline (C = z, O = y, CN = x)"
+    )]
+    pub name_format: NameFormat,
+
+    /// Time zone of displayed dates
+    #[arg(
+        long,
+        value_name = "ZONE",
+        default_value = "utc",
+        help = "Time zone of displayed dates: utc, local or an offset such as +02:00"
+    )]
+    pub time_zone: TimeZone,
+
     /// Verbose output
     #[arg(long, help = "Enable verbose output with detailed information")]
     pub verbose: bool,
 }
 
+/// Arguments for checking the expiry of the certificates in a PFX/P12 file
+#[derive(clap::Args, Debug, Clone)]
+pub struct CheckArgs {
+    /// Path to the PFX/P12 file, or `-` to read from stdin
+    #[arg(
+        long,
+        help = "Path to the PFX/P12 certificate file ('-' reads DER from stdin)"
+    )]
+    pub pfx: String,
+
+    /// Password for the PFX/P12 file
+    #[command(flatten)]
+    pub password: PasswordArgs,
+
+    /// Load OpenSSL's legacy provider for RC2/RC4/DES encrypted PFX files
+    #[arg(
+        long,
+        help = "Allow legacy PFX encryption (RC2-40, RC4, DES) via OpenSSL's legacy provider"
+    )]
+    pub legacy: bool,
+
+    /// Days before expiry that raise a WARNING
+    #[arg(
+        long,
+        value_name = "DAYS",
+        default_value_t = 30,
+        help = "Report WARNING when a certificate expires in fewer than DAYS days"
+    )]
+    pub warn_days: u32,
+
+    /// Days before expiry that raise a CRITICAL status
+    #[arg(
+        long,
+        value_name = "DAYS",
+        default_value_t = 7,
+        help = "Report CRITICAL when a certificate expires in fewer than DAYS days"
+    )]
+    pub critical_days: u32,
+
+    /// Prometheus textfile-collector output
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Write Prometheus metrics for node_exporter's textfile collector to PATH (e.g. forge.prom)"
+    )]
+    pub metrics_file: Option<PathBuf>,
+
+    /// Verbose output
+    #[arg(long, help = "List every certificate after the status line")]
+    pub verbose: bool,
+}
+
+impl CheckArgs {
+    /// Get the validated expiry thresholds
+    pub fn thresholds(&self) -> Result<ExpiryThresholds, ConversionError> {
+        ExpiryThresholds::new(self.warn_days, self.critical_days)
+    }
+}
+
+impl PackArgs {
+    /// Get the output PFX path, with default
+    pub fn output_path(&self) -> &str {
+        self.out.as_deref().unwrap_or("certificate.pfx")
+    }
+
+    /// Validate all input arguments before packing
+    pub fn validate(&self) -> Result<(), ConversionError> {
+        let inputs = [Some(&self.key), Some(&self.cert), self.chain.as_ref()];
+        for path in inputs.into_iter().flatten() {
+            if !Path::new(path).is_file() {
+                return Err(ConversionError::FileNotFound(path.clone()));
+            }
+        }
+
+        let out_path = Path::new(self.output_path());
+        if out_path.is_dir() {
+            return Err(ConversionError::InvalidFormat(format!(
+                "Output path '{}' is a directory",
+                self.output_path()
+            )));
+        }
+
+        Ok(())
+    }
+}
+
 impl Args {
-    /// Get the password, defaulting to empty string if none provided
-    pub fn password(&self) -> &str {
-        self.password.as_deref().unwrap_or("")
+    /// Get the PFX input path (the first one in batch mode)
+    pub fn pfx_path(&self) -> &str {
+        self.pfx.first().map_or("", String::as_str)
+    }
+
+    /// Check whether several PFX files are converted, each into its own subdirectory
+    ///
+    /// This is the case for more than one `--pfx`, a directory, a glob pattern
+    /// or `--recursive`, however many files they turn out to hold.
+    pub fn is_batch(&self) -> bool {
+        self.pfx.len() > 1
+            || self.recursive
+            || self
+                .pfx
+                .iter()
+                .any(|input| is_pattern(input) || Path::new(input).is_dir())
+    }
+
+    /// Arguments for converting one input of a batch into `out_dir`
+    ///
+    /// A password read once for the batch replaces the original source, as
+    /// file descriptors and prompts cannot be read again.
+    pub fn for_input(&self, input: &Path, out_dir: &Path, password: Option<&str>) -> Self {
+        let mut args = self.clone();
+        args.pfx = vec![input.display().to_string()];
+        args.out = Some(out_dir.display().to_string());
+        args.recursive = false;
+        if let Some(password) = password {
+            args.password = PasswordArgs {
+                password: Some(password.to_string()),
+                ..PasswordArgs::default()
+            };
+        }
+        args
+    }
+
+    /// Check whether the PFX data should be read from stdin
+    pub fn reads_stdin(&self) -> bool {
+        self.pfx_path() == "-"
     }
 
     /// Get the output directory, defaulting to current directory
@@ -80,32 +519,81 @@ impl Args {
             .unwrap_or("certificate_with_key.pem")
     }
 
+    /// Get the DER private key filename
+    ///
+    /// A custom `--key-file` is used as is when only DER is written; alongside
+    /// PEM its extension is replaced with `.der`.
+    pub fn key_der_filename(&self) -> String {
+        self.der_filename(self.key_file.as_deref(), self.key_filename(), "der")
+    }
+
+    /// Get the DER certificate filename, following the same rules with `.cer`
+    pub fn cert_der_filename(&self) -> String {
+        self.der_filename(self.cert_file.as_deref(), self.cert_filename(), "cer")
+    }
+
+    fn der_filename(&self, custom: Option<&str>, pem_name: &str, extension: &str) -> String {
+        match custom {
+            Some(name) if self.encoding == OutputEncoding::Der => name.to_string(),
+            _ => Path::new(pem_name)
+                .with_extension(extension)
+                .to_string_lossy()
+                .to_string(),
+        }
+    }
+
+    /// Validate the options of a batch conversion; each input is validated when converted
+    pub fn validate_batch(&self) -> Result<(), ConversionError> {
+        if self.pfx.iter().any(|input| input == "-") {
+            return Err(ConversionError::InvalidOption(
+                "stdin ('-') holds a single PFX file and cannot be combined with other inputs"
+                    .to_string(),
+            ));
+        }
+        if self.stdout.is_some() {
+            return Err(ConversionError::InvalidOption(
+                "--stdout streams a single PFX file; use --out for several inputs".to_string(),
+            ));
+        }
+        if self.output_format == OutputFormat::Json {
+            return Err(ConversionError::InvalidOption(
+                "--output-format json reports on a single PFX file".to_string(),
+            ));
+        }
+        if self.metrics_file.is_some() {
+            return Err(ConversionError::InvalidOption(
+                "--metrics-file describes a single PFX file".to_string(),
+            ));
+        }
+
+        let out_dir = Path::new(self.output_dir());
+        if out_dir.exists() && !out_dir.is_dir() {
+            return Err(ConversionError::InvalidFormat(format!(
+                "Output path '{}' exists but is not a directory",
+                self.output_dir()
+            )));
+        }
+        Ok(())
+    }
+
     /// Validate all input arguments before starting conversion
     pub fn validate(&self) -> Result<(), ConversionError> {
-        // Validate PFX file path
-        let pfx_path = Path::new(&self.pfx);
+        // Validate PFX file path (stdin has nothing to check up front)
+        let pfx_path = Path::new(self.pfx_path());
 
         // Check if the path exists
-        if !pfx_path.exists() {
-            return Err(ConversionError::FileNotFound(self.pfx.clone()));
+        if !self.reads_stdin() && !pfx_path.exists() {
+            return Err(ConversionError::FileNotFound(self.pfx_path().to_string()));
         }
 
         // Check if it's a file
-        if !pfx_path.is_file() {
+        if !self.reads_stdin() && !pfx_path.is_file() {
             return Err(ConversionError::InvalidFormat(format!(
                 "'{}' is not a file",
-                self.pfx
+                self.pfx_path()
             )));
         }
 
-        // Validate file extension (if available)
-        if let Some(ext) = pfx_path.extension().and_then(|e| e.to_str()) {
-            let ext = ext.to_lowercase();
-            if ext != "pfx" && ext != "p12" {
-                return Err(ConversionError::InvalidFileExtension(ext));
-            }
-        }
-
         // Validate output directory
         let out_dir = Path::new(self.output_dir());
         if out_dir.exists() && !out_dir.is_dir() {
@@ -115,15 +603,70 @@ impl Args {
             )));
         }
 
-        // Password validation (if explicitly provided)
-        if let Some(ref pwd) = self.password {
-            if pwd.is_empty() {
-                // If password is explicitly provided but empty, warn user
-                // Since empty password is valid in some cases, we don't return an error
-                eprintln!(
-                    "Warning: Empty password provided. If the PFX file requires a password, conversion might fail."
-                );
+        // DER holds a single object, so bundles and streams stay PEM
+        if self.encoding != OutputEncoding::Pem {
+            if self.combined && !self.encoding.writes_pem() {
+                return Err(ConversionError::InvalidOption(
+                    "--combined writes a PEM bundle; use --encoding both to add DER files"
+                        .to_string(),
+                ));
+            }
+            if self.encoding == OutputEncoding::Both
+                && (self.key_der_filename() == self.key_filename()
+                    || self.cert_der_filename() == self.cert_filename())
+            {
+                return Err(ConversionError::InvalidOption(
+                    "PEM and DER output would share a filename; give --key-file and \
+                     --cert-file a PEM extension such as .pem"
+                        .to_string(),
+                ));
             }
+            if self.stdout.is_some() {
+                return Err(ConversionError::InvalidOption(
+                    "--stdout only writes PEM and cannot be combined with --encoding".to_string(),
+                ));
+            }
+        }
+
+        // Catch invalid Secret names before any file is written
+        if let Some(ref name) = self.kubernetes.secret_name {
+            validate_name("Secret name", name)?;
+        }
+        if let Some(ref namespace) = self.kubernetes.namespace {
+            validate_name("Namespace", namespace)?;
+        }
+
+        // Secrets are rendered from the unencrypted key
+        if self.kubernetes.secret_name.is_some() && self.key_encryption.encrypt_key {
+            return Err(ConversionError::InvalidOption(
+                "--k8s-secret needs an unencrypted private key and cannot be combined with \
+                 --encrypt-key"
+                    .to_string(),
+            ));
+        }
+        if self.stdout == Some(StdoutPart::Secret) && self.kubernetes.secret_name.is_none() {
+            return Err(ConversionError::InvalidOption(
+                "--stdout secret needs a Secret name from --k8s-secret".to_string(),
+            ));
+        }
+
+        // Only PKCS#8 keys can be encrypted
+        if self.key_encryption.encrypt_key && self.key_format != KeyFormat::Pkcs8 {
+            return Err(ConversionError::InvalidOption(format!(
+                "--encrypt-key writes PKCS#8 keys and cannot be combined with the {} key format",
+                self.key_format
+            )));
+        }
+
+        // Password validation (if explicitly provided)
+        if let Some(ref pwd) = self.password.password
+            && pwd.is_empty()
+        {
+            // If password is explicitly provided but empty, warn user
+            // Since empty password is valid in some cases, we don't return an error
+            eprintln!(
+                "Warning: Empty password provided. If the PFX file requires a password, conversion might fail."
+            );
         }
 
         Ok(())
@@ -0,0 +1,162 @@
+use crate::error::ConversionError;
+use console::Term;
+use std::env;
+use std::fs;
+use std::io::{IsTerminal, Write};
+
+/// Password sources shared by every command that reads or writes a PFX file
+///
+/// At most one source may be given. When none is, callers fall back to an
+/// empty password and may prompt on an interactive terminal.
+#[derive(clap::Args, Debug, Default, Clone)]
+#[group(id = "password_source", multiple = false)]
+pub struct PasswordArgs {
+    /// Password on the command line (visible in shell history and `ps`)
+    #[arg(
+        long,
+        help = "Password for the PFX file (visible in shell history; prefer the options below)"
+    )]
+    pub password: Option<String>,
+
+    /// Name of an environment variable holding the password
+    #[arg(
+        long,
+        value_name = "NAME",
+        help = "Read the PFX password from the named environment variable"
+    )]
+    pub password_env: Option<String>,
+
+    /// Path to a file whose first line is the password
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Read the PFX password from the first line of a file"
+    )]
+    pub password_file: Option<String>,
+
+    /// Open file descriptor to read the password from
+    #[arg(
+        long,
+        value_name = "FD",
+        help = "Read the PFX password from an open file descriptor (Unix only)"
+    )]
+    pub password_fd: Option<i32>,
+}
+
+impl PasswordArgs {
+    /// Read the password from whichever source was given, if any
+    pub fn resolve(&self) -> Result<Option<String>, ConversionError> {
+        if let Some(ref password) = self.password {
+            return Ok(Some(password.clone()));
+        }
+
+        if let Some(ref name) = self.password_env {
+            return env::var(name).map(Some).map_err(|e| {
+                ConversionError::PasswordInput(format!(
+                    "Cannot read environment variable '{name}': {e}"
+                ))
+            });
+        }
+
+        if let Some(ref path) = self.password_file {
+            let content =
+                fs::read_to_string(path).map_err(|e| ConversionError::FileRead(path.clone(), e))?;
+            return Ok(Some(first_line(&content)));
+        }
+
+        if let Some(fd) = self.password_fd {
+            return read_fd(fd).map(Some);
+        }
+
+        Ok(None)
+    }
+
+    /// Reject a password source that competes with the PFX input for stdin
+    pub fn check_input(&self, pfx_path: &str) -> Result<(), ConversionError> {
+        if pfx_path == "-" && self.password_fd == Some(0) {
+            return Err(ConversionError::InvalidOption(
+                "--password-fd 0 and --pfx - both read stdin; pass the password on another \
+                 descriptor"
+                    .to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Check whether the user can be prompted for a password
+pub fn can_prompt() -> bool {
+    std::io::stdin().is_terminal() && Term::stderr().is_term()
+}
+
+/// Prompt for a password on the terminal without echoing it
+pub fn prompt_password(prompt: &str) -> Result<String, ConversionError> {
+    if !can_prompt() {
+        return Err(ConversionError::PasswordInput(
+            "No password provided and no terminal available to prompt for one".to_string(),
+        ));
+    }
+
+    let mut term = Term::stderr();
+    write!(term, "{prompt}")
+        .and_then(|_| term.flush())
+        .and_then(|_| term.read_secure_line())
+        .map_err(|e| ConversionError::PasswordInput(format!("Failed to read password: {e}")))
+}
+
+/// Prompt for a new password twice, requiring both entries to match
+pub fn prompt_new_password(prompt: &str) -> Result<String, ConversionError> {
+    let password = prompt_password(prompt)?;
+    let confirmation = prompt_password("Confirm password: ")?;
+
+    if password != confirmation {
+        return Err(ConversionError::PasswordInput(
+            "Passwords do not match".to_string(),
+        ));
+    }
+
+    Ok(password)
+}
+
+/// Use the first line of a password file or stream, without its line ending
+pub(crate) fn first_line(content: &str) -> String {
+    content.lines().next().unwrap_or_default().to_string()
+}
+
+#[cfg(unix)]
+fn read_fd(fd: i32) -> Result<String, ConversionError> {
+    use std::io::Read;
+    use std::os::fd::FromRawFd;
+
+    if fd < 0 {
+        return Err(ConversionError::PasswordInput(format!(
+            "Invalid file descriptor: {fd}"
+        )));
+    }
+
+    // Read from a duplicate, so the descriptor itself is never owned or closed
+    // here; this also fails cleanly when it is not open
+    // SAFETY: dup only reads the descriptor table
+    let duplicate = unsafe { libc::dup(fd) };
+    if duplicate < 0 {
+        return Err(ConversionError::PasswordInput(format!(
+            "File descriptor {fd} is not open: {}",
+            std::io::Error::last_os_error()
+        )));
+    }
+    // SAFETY: dup returned a new descriptor that nothing else owns
+    let mut file = unsafe { fs::File::from_raw_fd(duplicate) };
+    let mut content = String::new();
+    file.read_to_string(&mut content).map_err(|e| {
+        ConversionError::PasswordInput(format!("Failed to read file descriptor {fd}: {e}"))
+    })?;
+
+    Ok(first_line(&content))
+}
+
+#[cfg(not(unix))]
+fn read_fd(_fd: i32) -> Result<String, ConversionError> {
+    Err(ConversionError::PasswordInput(
+        "--password-fd is only supported on Unix platforms".to_string(),
+    ))
+}
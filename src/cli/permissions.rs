@@ -0,0 +1,175 @@
+use crate::error::ConversionError;
+use crate::writer::{DEFAULT_CERT_MODE, DEFAULT_KEY_MODE, FilePermissions};
+
+/// Permissions and ownership of written files
+#[derive(clap::Args, Debug, Default, Clone)]
+pub struct PermissionArgs {
+    /// Mode for files holding the private key (octal)
+    #[arg(
+        long,
+        value_name = "MODE",
+        value_parser = parse_mode,
+        help = "File mode for private key, combined and Secret files, in octal (default 0600)"
+    )]
+    pub key_mode: Option<u32>,
+
+    /// Mode for certificate files (octal)
+    #[arg(
+        long,
+        value_name = "MODE",
+        value_parser = parse_mode,
+        help = "File mode for certificate and chain files, in octal (default 0644)"
+    )]
+    pub cert_mode: Option<u32>,
+
+    /// Owner of written files, by name or numeric id
+    #[arg(
+        long,
+        value_name = "USER",
+        help = "Give written files to this user (name or uid; usually needs root)"
+    )]
+    pub owner: Option<String>,
+
+    /// Group of written files, by name or numeric id
+    #[arg(
+        long,
+        value_name = "GROUP",
+        help = "Give written files to this group (name or gid)"
+    )]
+    pub group: Option<String>,
+}
+
+impl PermissionArgs {
+    /// Resolve modes and look up the owner and group ids
+    pub fn resolve(&self) -> Result<FilePermissions, ConversionError> {
+        Ok(FilePermissions {
+            key_mode: self.key_mode.unwrap_or(DEFAULT_KEY_MODE),
+            cert_mode: self.cert_mode.unwrap_or(DEFAULT_CERT_MODE),
+            owner: self.owner.as_deref().map(lookup_user).transpose()?,
+            group: self.group.as_deref().map(lookup_group).transpose()?,
+        })
+    }
+}
+
+/// Parse an octal file mode such as `600` or `0640`
+///
+/// Only permission bits are allowed; setuid, setgid and sticky bits have no
+/// place on key and certificate files.
+fn parse_mode(value: &str) -> Result<u32, ConversionError> {
+    let digits = value.trim_start_matches("0o");
+    let mode = u32::from_str_radix(digits, 8).map_err(|_| {
+        ConversionError::InvalidOption(format!("'{value}' is not an octal file mode"))
+    })?;
+    if mode > 0o777 {
+        return Err(ConversionError::InvalidOption(format!(
+            "'{value}' sets special mode bits; use permission bits only (at most 0777)"
+        )));
+    }
+    Ok(mode)
+}
+
+#[cfg(unix)]
+fn lookup_user(name: &str) -> Result<u32, ConversionError> {
+    if let Ok(uid) = name.parse() {
+        return Ok(uid);
+    }
+
+    // SAFETY: sysconf only reads a configuration value
+    let size_hint = unsafe { libc::sysconf(libc::_SC_GETPW_R_SIZE_MAX) };
+    lookup_entry(name, "user", size_hint, libc::getpwnam_r, |entry| {
+        entry.pw_uid
+    })
+}
+
+#[cfg(unix)]
+fn lookup_group(name: &str) -> Result<u32, ConversionError> {
+    if let Ok(gid) = name.parse() {
+        return Ok(gid);
+    }
+
+    // SAFETY: sysconf only reads a configuration value
+    let size_hint = unsafe { libc::sysconf(libc::_SC_GETGR_R_SIZE_MAX) };
+    lookup_entry(name, "group", size_hint, libc::getgrnam_r, |entry| {
+        entry.gr_gid
+    })
+}
+
+/// Signature shared by `getpwnam_r` and `getgrnam_r`
+#[cfg(unix)]
+type LookupFn<T> = unsafe extern "C" fn(
+    *const libc::c_char,
+    *mut T,
+    *mut libc::c_char,
+    libc::size_t,
+    *mut *mut T,
+) -> libc::c_int;
+
+/// Largest buffer offered to a lookup before giving up
+#[cfg(unix)]
+const MAX_LOOKUP_BUFFER: usize = 1 << 20;
+
+/// Find a passwd or group entry by name with a reentrant lookup
+///
+/// The strings of the entry live in a buffer owned here, which grows for as
+/// long as the lookup reports `ERANGE`.
+#[cfg(unix)]
+fn lookup_entry<T>(
+    name: &str,
+    kind: &str,
+    size_hint: libc::c_long,
+    lookup: LookupFn<T>,
+    id: impl Fn(&T) -> u32,
+) -> Result<u32, ConversionError> {
+    let c_name = std::ffi::CString::new(name)
+        .map_err(|_| ConversionError::InvalidOption(format!("Invalid {kind} name '{name}'")))?;
+    let mut buffer: Vec<libc::c_char> = vec![0; usize::try_from(size_hint).unwrap_or(0).max(1024)];
+
+    loop {
+        let mut entry = std::mem::MaybeUninit::<T>::uninit();
+        let mut result = std::ptr::null_mut();
+        // SAFETY: every pointer is valid for the call and the buffer length is
+        // its real size; on success `result` points at `entry`.
+        let code = unsafe {
+            lookup(
+                c_name.as_ptr(),
+                entry.as_mut_ptr(),
+                buffer.as_mut_ptr(),
+                buffer.len(),
+                &mut result,
+            )
+        };
+
+        match code {
+            0 if result.is_null() => {
+                return Err(ConversionError::InvalidOption(format!(
+                    "Unknown {kind} '{name}'"
+                )));
+            }
+            // SAFETY: the lookup succeeded and filled in the entry
+            0 => return Ok(id(unsafe { entry.assume_init_ref() })),
+            libc::ERANGE if buffer.len() < MAX_LOOKUP_BUFFER => {
+                buffer.resize(buffer.len() * 2, 0);
+            }
+            code => {
+                return Err(ConversionError::InvalidOption(format!(
+                    "Could not look up {kind} '{name}': {}",
+                    std::io::Error::from_raw_os_error(code)
+                )));
+            }
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn lookup_user(_name: &str) -> Result<u32, ConversionError> {
+    Err(ConversionError::InvalidOption(
+        "--owner is only supported on Unix platforms".to_string(),
+    ))
+}
+
+#[cfg(not(unix))]
+fn lookup_group(_name: &str) -> Result<u32, ConversionError> {
+    Err(ConversionError::InvalidOption(
+        "--group is only supported on Unix platforms".to_string(),
+    ))
+}
@@ -0,0 +1,100 @@
+use crate::kubernetes::ManifestFormat;
+use crate::openssl::{KeyFormat, NameFormat, OutputEncoding};
+use crate::output::OutputFormat;
+use clap::ValueEnum;
+use clap::builder::PossibleValue;
+
+// Only the CLI layer depends on clap; option enums of the other modules
+// become command-line values here.
+
+impl ValueEnum for KeyFormat {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[
+            KeyFormat::Pkcs8,
+            KeyFormat::Pkcs1,
+            KeyFormat::Sec1,
+            KeyFormat::Traditional,
+        ]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(match self {
+            KeyFormat::Pkcs8 => PossibleValue::new("pkcs8")
+                .help("PKCS#8 (`BEGIN PRIVATE KEY`), works for every key type"),
+            KeyFormat::Pkcs1 => {
+                PossibleValue::new("pkcs1").help("PKCS#1 (`BEGIN RSA PRIVATE KEY`), RSA keys only")
+            }
+            KeyFormat::Sec1 => {
+                PossibleValue::new("sec1").help("SEC1 (`BEGIN EC PRIVATE KEY`), EC keys only")
+            }
+            KeyFormat::Traditional => {
+                PossibleValue::new("traditional").help("PKCS#1 for RSA keys and SEC1 for EC keys")
+            }
+        })
+    }
+}
+
+impl ValueEnum for OutputEncoding {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[
+            OutputEncoding::Pem,
+            OutputEncoding::Der,
+            OutputEncoding::Both,
+        ]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(match self {
+            OutputEncoding::Pem => PossibleValue::new("pem").help("Base64 PEM text files"),
+            OutputEncoding::Der => PossibleValue::new("der")
+                .help("Binary DER files (`.der` keys, `.cer` certificates)"),
+            OutputEncoding::Both => PossibleValue::new("both").help("Both PEM and DER files"),
+        })
+    }
+}
+
+impl ValueEnum for NameFormat {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[NameFormat::Rfc4514, NameFormat::Oneline]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(match self {
+            NameFormat::Rfc4514 => PossibleValue::new("rfc4514")
+                .help("RFC 4514, most specific attribute first: `CN=example.com,O=Example,C=US`"),
+            NameFormat::Oneline => PossibleValue::new("oneline").help(
+                "OpenSSL's one-line style, in stored order: `C = US, O = Example, CN = example.com`",
+            ),
+        })
+    }
+}
+
+impl ValueEnum for OutputFormat {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[OutputFormat::Text, OutputFormat::Json]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(match self {
+            OutputFormat::Text => PossibleValue::new("text").help("Decorated tables for people"),
+            OutputFormat::Json => {
+                PossibleValue::new("json").help("A JSON report on stdout for automation")
+            }
+        })
+    }
+}
+
+impl ValueEnum for ManifestFormat {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[ManifestFormat::Yaml, ManifestFormat::Json]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(match self {
+            ManifestFormat::Yaml => {
+                PossibleValue::new("yaml").help("YAML, as used with `kubectl apply -f`")
+            }
+            ManifestFormat::Json => PossibleValue::new("json").help("JSON"),
+        })
+    }
+}
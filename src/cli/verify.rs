@@ -0,0 +1,39 @@
+use crate::openssl::TrustStore;
+use std::path::PathBuf;
+
+/// Options for verifying the certificate chain against a trust store
+#[derive(clap::Args, Debug, Default, Clone)]
+pub struct VerifyArgs {
+    /// Verify the chain against the system trust store
+    #[arg(
+        long,
+        help = "Verify that the certificate chains to a trusted root (system store by default)"
+    )]
+    pub verify: bool,
+
+    /// PEM bundle of trusted CA certificates
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Verify against the CA certificates in this PEM file (implies --verify)"
+    )]
+    pub ca_file: Option<PathBuf>,
+
+    /// Directory of hashed trusted CA certificates
+    #[arg(
+        long,
+        value_name = "DIR",
+        help = "Verify against a hashed CA directory, as made by `openssl rehash` (implies --verify)"
+    )]
+    pub ca_dir: Option<PathBuf>,
+}
+
+impl VerifyArgs {
+    /// Get the trust store to verify against, if verification was requested
+    pub fn trust_store(&self) -> Option<TrustStore> {
+        (self.verify || self.ca_file.is_some() || self.ca_dir.is_some()).then(|| TrustStore {
+            ca_file: self.ca_file.clone(),
+            ca_dir: self.ca_dir.clone(),
+        })
+    }
+}
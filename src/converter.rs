@@ -1,14 +1,85 @@
-use crate::cli::Args;
+use crate::cli::{
+    Args, BatchDirName, CheckArgs, InspectArgs, PackArgs, PasswordArgs, StdoutPart, can_prompt,
+    expand_inputs, prompt_new_password, prompt_password,
+};
 use crate::error::ConversionError;
-use crate::openssl::{PemFormatter, PfxParser};
-use crate::output::{OutputConfig, OutputHandler, ProgressReporter};
+use crate::kubernetes::{ManifestFormat, TlsSecret, dns_label};
+use crate::openssl::{
+    CertificateInfo, ChainReport, DerFormatter, ExpiryStatus, InputFormat, KeyEncryption,
+    KeyOutput, LintFinding, NameFormat, OutputEncoding, ParsedPfx, PemFormatter, PemParser,
+    PfxFormatter, PfxIdentity, PfxParser, Severity, TrustStore, Verification,
+};
+use crate::output::{
+    BatchEntry, CertificateMetrics, ConversionReport, OutputConfig, OutputHandler,
+    ProgressReporter, inspection_json, prometheus_metrics,
+};
+use crate::policy::Policy;
+use crate::writer::{FileKind, FileWriter, is_shared_writable};
+use openssl::x509::X509;
 use std::fs;
-use std::path::Path;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 
 /// Main conversion function that orchestrates the PFX to PEM conversion
 pub fn convert_pfx_to_pem(args: Args) -> Result<(), Box<dyn std::error::Error>> {
+    if args.is_batch() {
+        return convert_batch(&args);
+    }
+    convert_file(&args, None).map(|_| ())
+}
+
+/// Settings shared by every input of a batch conversion
+struct BatchContext {
+    /// The key encryption, resolved once so the passphrase is asked for once
+    key_encryption: Option<KeyEncryption>,
+    /// How output subdirectories are named
+    dir_name: BatchDirName,
+    /// Output subdirectories handed out so far
+    output_dirs: Vec<PathBuf>,
+}
+
+impl BatchContext {
+    /// Pick the output subdirectory of an input from its file name or certificate
+    fn output_dir(&mut self, args: &Args, identities: &[PfxIdentity]) -> PathBuf {
+        let input = Path::new(args.pfx_path());
+        let stem = input
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let name = match self.dir_name {
+            BatchDirName::File => stem,
+            BatchDirName::Cn => identities
+                .iter()
+                .find_map(|identity| identity.contents.certificate_info()?.common_name)
+                .unwrap_or(stem),
+        };
+        let name = match safe_dir_name(&name) {
+            name if name.is_empty() => "pfx".to_string(),
+            name => name,
+        };
+
+        let root = Path::new(args.output_dir());
+        let mut unique = root.join(&name);
+        let mut count = 1;
+        while self.output_dirs.contains(&unique) {
+            count += 1;
+            unique = root.join(format!("{name}-{count}"));
+        }
+        self.output_dirs.push(unique.clone());
+        unique
+    }
+}
+
+/// Convert a single PFX file, returning the number of files written
+///
+/// Within a batch, the key encryption comes from the batch and the summary is
+/// left to the batch.
+fn convert_file(
+    args: &Args,
+    mut batch: Option<&mut BatchContext>,
+) -> Result<usize, Box<dyn std::error::Error>> {
     // Set up output handling
-    let output_config = OutputConfig::from_args(&args);
+    let output_config = OutputConfig::from_args(args);
     let mut output = OutputHandler::new(output_config.clone());
     let progress = ProgressReporter::new(&output_config);
 
@@ -24,40 +95,460 @@ pub fn convert_pfx_to_pem(args: Args) -> Result<(), Box<dyn std::error::Error>>
         return Err(Box::new(e));
     }
 
-    output.info(&format!("Input file: {}", args.pfx))?;
-
-    // Create output directory
-    let output_dir = args.output_dir();
-    progress.reading_file(&args.pfx);
+    output.info(&format!("Input file: {}", args.pfx_path()))?;
 
-    fs::create_dir_all(output_dir).map_err(|e| {
-        progress.error(&format!("Failed to create output directory: {e}"));
-        ConversionError::DirectoryCreation(output_dir.to_string(), e)
+    let permissions = args.permissions.resolve().map_err(|e| {
+        progress.error(&format!("Validation failed: {e}"));
+        e
     })?;
+    let policy = args
+        .policy
+        .as_deref()
+        .map(Policy::load)
+        .transpose()
+        .map_err(|e| {
+            progress.error(&format!("Validation failed: {e}"));
+            e
+        })?;
 
-    output.info(&format!("Output directory: {output_dir}"))?;
+    progress.reading_file(args.pfx_path());
+    let pfx_data = read_pfx(args.pfx_path(), &args.password, &progress)?;
+
+    let input_format = InputFormat::detect(&pfx_data);
+    output.info(&format!("Detected input format: {input_format}"))?;
 
     // Parse the PFX file
     output.status("Converting PFX to PEM format...")?;
     progress.parsing();
 
-    let parsed = PfxParser::parse_file(&args.pfx, args.password()).map_err(|e| {
-        progress.error(&format!("Failed to parse PFX: {e}"));
-        e
-    })?;
+    let PfxContents {
+        mut identities,
+        incomplete,
+        used_legacy_provider,
+    } = parse_pfx(&pfx_data, &args.password, args.legacy, &progress)?;
 
     output.info("Successfully parsed PFX file")?;
+    if let Some(ref warning) = incomplete {
+        output.warning(warning)?;
+    }
 
-    // Show basic cert info in verbose mode, detailed table will be shown in summary
-    if output_config.verbose {
-        output.info("Certificate information:")?;
-        let cert_info_text = parsed.cert_info();
-        for line in cert_info_text.lines() {
-            output.info(line)?;
+    let encryption = PfxParser::encryption_info(&pfx_data).ok();
+    if let Some(ref encryption) = encryption {
+        output.info(&format!("PFX encryption: {}", encryption.summary()))?;
+
+        let legacy = encryption.legacy_algorithms();
+        if !legacy.is_empty() {
+            let provider = if used_legacy_provider {
+                "; it was read with OpenSSL's legacy provider"
+            } else {
+                ""
+            };
+            output.warning(&format!(
+                "PFX file uses legacy encryption ({}){provider}",
+                legacy.join(", ")
+            ))?;
+        }
+    }
+
+    // A key that does not belong to its certificate makes the output unusable
+    for identity in &identities {
+        identity
+            .contents
+            .check_key_match(args.sign_check)
+            .map_err(|e| {
+                progress.error(&format!("Key check failed: {e}"));
+                e
+            })?;
+    }
+    if identities
+        .iter()
+        .any(|identity| identity.contents.key_matches_certificate() == Some(true))
+    {
+        output.info("Private key matches the certificate")?;
+    }
+
+    // Put the chain in leaf-to-root order before anything is written
+    let mut chain_messages = Vec::new();
+    for identity in &mut identities {
+        let report = identity.contents.order_chain(args.omit_root);
+        for message in chain_report_messages(&report, args.name_format) {
+            if !chain_messages.contains(&message) {
+                chain_messages.push(message);
+            }
+        }
+    }
+    for (is_warning, message) in chain_messages {
+        if is_warning {
+            output.warning(&message)?;
+        } else {
+            output.info(&message)?;
+        }
+    }
+
+    // Check the chain against the trust store before writing anything
+    let verifications = match args.verify.trust_store() {
+        Some(trust_store) => {
+            verify_identities(&identities, &trust_store, &mut output).map_err(|e| {
+                progress.error(&format!("Verification failed: {e}"));
+                e
+            })?
+        }
+        None => vec![None; identities.len()],
+    };
+
+    // Metrics are written even for untrusted chains, so monitoring sees them
+    if let Some(ref path) = args.metrics_file {
+        let metrics: Vec<_> = identities
+            .iter()
+            .zip(&verifications)
+            .filter_map(|(identity, verification)| {
+                CertificateMetrics::new(
+                    args.pfx_path(),
+                    &identity.contents,
+                    verification.as_ref().map(Verification::is_trusted),
+                )
+            })
+            .collect();
+        write_metrics(path, &metrics)?;
+        output.info(&format!("Prometheus metrics: {}", path.display()))?;
+    }
+
+    if let Some(e) = untrusted_error(&identities, &verifications, args.name_format) {
+        progress.error(&format!("Verification failed: {e}"));
+        return Err(Box::new(e));
+    }
+
+    // Flag weak keys and signatures and certificates outside their validity period
+    let mut findings: Vec<LintFinding> = Vec::new();
+    for identity in &identities {
+        for finding in identity.contents.lint() {
+            if !findings.contains(&finding) {
+                findings.push(finding);
+            }
+        }
+    }
+
+    // Organization rules are enforced, unlike the built-in lint
+    if let Some(ref policy) = policy {
+        let mut violations: Vec<LintFinding> = Vec::new();
+        for identity in &identities {
+            for finding in policy.evaluate(&identity.contents, encryption.as_ref()) {
+                if !violations.contains(&finding) {
+                    violations.push(finding);
+                }
+            }
+        }
+        if !violations.is_empty() {
+            let e = ConversionError::Policy(format!(
+                "violated rules: {}",
+                rule_names(&violations).join(", ")
+            ));
+            progress.error(&format!("Policy violated: {e}"));
+            findings.extend(violations);
+            output.print_findings(&findings)?;
+            return Err(Box::new(e));
+        }
+        output.info("The bundle satisfies the policy file")?;
+    }
+
+    if args.strict
+        && let Some(e) = lint_error(&findings)
+    {
+        progress.error(&format!("Lint failed: {e}"));
+        output.print_findings(&findings)?;
+        return Err(Box::new(e));
+    }
+
+    // Ask for the key passphrase only once the PFX is known to be readable
+    let key_encryption = match batch {
+        Some(ref batch) => batch.key_encryption.clone(),
+        None => progress
+            .suspend(|| args.key_encryption.resolve())
+            .map_err(|e| {
+                progress.error(&format!("Failed to read key passphrase: {e}"));
+                e
+            })?,
+    };
+    if let Some(ref encryption) = key_encryption {
+        output.info(&format!(
+            "Private key will be encrypted ({})",
+            encryption.description()
+        ))?;
+    }
+    let key_output = KeyOutput {
+        format: args.key_format,
+        encryption: key_encryption,
+    };
+
+    // Reject a key format that does not fit the key type before writing anything
+    for key in identities
+        .iter()
+        .filter_map(|i| i.contents.private_key.as_ref())
+    {
+        key_output.format.resolve(key).map_err(|e| {
+            progress.error(&format!("Unsupported key format: {e}"));
+            e
+        })?;
+    }
+
+    // Files holding several identities get one output set per alias
+    let split_identities = identities.len() > 1;
+    if split_identities {
+        output.info(&format!(
+            "Found {} identities (key/certificate pairs) in PFX file",
+            identities.len()
+        ))?;
+    }
+
+    let mut dir_names = Vec::new();
+    for (index, identity) in identities.iter().enumerate() {
+        let dir_name = identity_dir_name(identity, index, &dir_names);
+        dir_names.push(dir_name);
+    }
+
+    // Stream PEM to stdout instead of writing files
+    if let Some(part) = args.stdout {
+        let mut stdout = io::stdout().lock();
+        for (index, (identity, dir_name)) in identities.iter().zip(&dir_names).enumerate() {
+            let pem = match secret_name(args, split_identities.then_some(dir_name)) {
+                Some(name) if part == StdoutPart::Secret => {
+                    let manifest = render_secret(args, &identity.contents, &name, &key_output)?;
+                    // Separate YAML documents when several Secrets are streamed
+                    let separator = if index > 0 && args.kubernetes.format == ManifestFormat::Yaml {
+                        "---\n"
+                    } else {
+                        ""
+                    };
+                    format!("{separator}{manifest}").into_bytes()
+                }
+                _ => stdout_pem(&identity.contents, part, args.chain, &key_output)?,
+            };
+            stdout
+                .write_all(&pem)
+                .and_then(|_| stdout.flush())
+                .map_err(|e| ConversionError::FileWrite("<stdout>".to_string(), e))?;
+        }
+        progress.complete();
+        output.print_findings(&findings)?;
+        output.success("PEM data written to stdout")?;
+        return Ok(0);
+    }
+
+    // Within a batch, each input gets its own subdirectory, named from what was read
+    let batch_args;
+    let args = match batch {
+        Some(ref mut batch) => {
+            let output_dir = batch.output_dir(args, &identities);
+            batch_args = args.for_input(Path::new(args.pfx_path()), &output_dir, None);
+            &batch_args
+        }
+        None => args,
+    };
+
+    // Create the output directory only once the PFX is known to convert
+    let output_dir = args.output_dir();
+    let created_dir = !Path::new(output_dir).exists();
+    fs::create_dir_all(output_dir).map_err(|e| {
+        progress.error(&format!("Failed to create output directory: {e}"));
+        ConversionError::DirectoryCreation(output_dir.to_string(), e)
+    })?;
+    output.info(&format!("Output directory: {output_dir}"))?;
+    if is_shared_writable(Path::new(output_dir)) {
+        output.warning(&format!(
+            "Output directory '{output_dir}' is writable by its group or others; \
+             other users could replace the written key material"
+        ))?;
+    }
+
+    // Write every file, undoing the whole run if any step fails
+    let mut writer = FileWriter::new(permissions).overwrite(args.force);
+    let result = write_identities(
+        args,
+        &identities,
+        &dir_names,
+        &key_output,
+        &progress,
+        &mut output,
+        &mut writer,
+    );
+    if let Err(e) = result {
+        progress.error(&format!("Conversion failed: {e}"));
+        for path in writer.rollback() {
+            output.warning(&format!(
+                "Could not restore '{}' after the failed conversion",
+                path.display()
+            ))?;
+        }
+        // Only a directory this run created may go, and only once it is empty
+        if created_dir {
+            let _ = fs::remove_dir(output_dir);
+        }
+        return Err(e);
+    }
+    writer.commit();
+
+    // Complete the process
+    progress.complete();
+    if batch.is_some() {
+        output.print_findings(&findings)?;
+        return Ok(writer.written().len());
+    }
+
+    // Print the beautiful summary, or the JSON report for automation
+    if output.is_json() {
+        let warnings = output.warnings().to_vec();
+        output.print_report(&ConversionReport {
+            input: args.pfx_path(),
+            input_format: input_format.to_string(),
+            identities: &identities,
+            key_output: &key_output,
+            encoding: args.encoding,
+            files: writer.written(),
+            warnings: &warnings,
+            findings: &findings,
+        })?;
+    } else {
+        output.print_summary(
+            &identities[0].contents,
+            &key_output,
+            args.encoding,
+            writer.written(),
+            &findings,
+        )?;
+    }
+
+    Ok(writer.written().len())
+}
+
+/// Convert every PFX file found in the inputs, each into its own subdirectory
+///
+/// A failed input is reported and skipped; the batch fails once every input
+/// has been tried.
+fn convert_batch(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+    let mut output = OutputHandler::new(OutputConfig::from_args(args));
+    args.validate_batch()?;
+
+    let inputs = expand_inputs(&args.pfx, args.recursive)?;
+    if inputs.is_empty() {
+        return Err(Box::new(ConversionError::InvalidOption(format!(
+            "no PFX files found in {}",
+            args.pfx.join(", ")
+        ))));
+    }
+    output.info(&format!("Found {} PFX files to convert", inputs.len()))?;
+
+    // File descriptors and prompts can be read only once, so every input shares them
+    let password = args.password.resolve()?;
+    let mut context = BatchContext {
+        key_encryption: args.key_encryption.resolve()?,
+        dir_name: args.dir_name,
+        output_dirs: Vec::new(),
+    };
+
+    let mut entries = Vec::new();
+    for (index, input) in inputs.iter().enumerate() {
+        output.status(&format!(
+            "[{}/{}] Converting {}",
+            index + 1,
+            inputs.len(),
+            input.display()
+        ))?;
+
+        let file_args = args.for_input(input, Path::new(args.output_dir()), password.as_deref());
+        let named = context.output_dirs.len();
+        let result = convert_file(&file_args, Some(&mut context)).map_err(|e| e.to_string());
+        if let Err(ref e) = result {
+            output.warning(&format!("Skipping {}: {e}", input.display()))?;
+        }
+        entries.push(BatchEntry {
+            input: input.clone(),
+            // Inputs that fail before they are named get no directory
+            output_dir: context.output_dirs.get(named).cloned(),
+            result,
+        });
+    }
+
+    output.print_batch_summary(&entries)?;
+    let failed = entries.iter().filter(|entry| entry.result.is_err()).count();
+    if failed > 0 {
+        return Err(Box::new(ConversionError::BatchFailed(
+            failed,
+            entries.len(),
+        )));
+    }
+    Ok(())
+}
+
+/// Write the output files for every identity
+fn write_identities(
+    args: &Args,
+    identities: &[PfxIdentity],
+    dir_names: &[String],
+    key_output: &KeyOutput,
+    progress: &ProgressReporter,
+    output: &mut OutputHandler,
+    writer: &mut FileWriter,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let output_dir = args.output_dir();
+    let split_identities = identities.len() > 1;
+    for (identity, dir_name) in identities.iter().zip(dir_names) {
+        let identity_dir = if split_identities {
+            let dir = Path::new(output_dir).join(dir_name);
+            fs::create_dir_all(&dir)
+                .map_err(|e| ConversionError::DirectoryCreation(dir.display().to_string(), e))?;
+            output.status(&format!(
+                "Extracting identity '{}' into {}",
+                identity.friendly_name.as_deref().unwrap_or(dir_name),
+                dir.display()
+            ))?;
+            dir
+        } else {
+            PathBuf::from(output_dir)
+        };
+
+        extract_identity(
+            args,
+            &identity.contents,
+            &identity_dir,
+            key_output,
+            progress,
+            output,
+            writer,
+        )?;
+
+        if let Some(name) = secret_name(args, split_identities.then_some(dir_name)) {
+            save_secret(
+                args,
+                &identity.contents,
+                &identity_dir,
+                &name,
+                key_output,
+                output,
+                writer,
+            )?;
         }
     }
 
-    output.print_cert_info(&parsed)?;
+    Ok(())
+}
+
+/// Write the PEM files for a single identity into `output_dir`
+fn extract_identity(
+    args: &Args,
+    parsed: &ParsedPfx,
+    output_dir: &Path,
+    key_output: &KeyOutput,
+    progress: &ProgressReporter,
+    output: &mut OutputHandler,
+    writer: &mut FileWriter,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Show basic cert info in verbose mode, detailed table will be shown in summary
+    output.info("Certificate information:")?;
+    for line in parsed.cert_info().lines() {
+        output.info(line)?;
+    }
+
+    output.print_cert_info(parsed)?;
 
     if parsed.has_chain() {
         output.info(&format!(
@@ -67,70 +558,655 @@ pub fn convert_pfx_to_pem(args: Args) -> Result<(), Box<dyn std::error::Error>>
     }
 
     // Set up file paths
-    let key_path = Path::new(output_dir).join(args.key_filename());
-    let cert_path = Path::new(output_dir).join(args.cert_filename());
-    let combined_path = Path::new(output_dir).join(args.combined_filename());
+    let key_path = output_dir.join(args.key_filename());
+    let cert_path = output_dir.join(args.cert_filename());
+    let combined_path = output_dir.join(args.combined_filename());
 
     // Convert and save private key
-    progress.extracting_key();
-    let private_key_pem = PemFormatter::private_key_to_pem(&parsed)?;
-    write_file(&key_path, &private_key_pem)?;
-    output.success(&format!("Private key saved to: {}", key_path.display()))?;
+    if parsed.has_private_key() {
+        progress.extracting_key();
+        if args.encoding.writes_pem() {
+            let private_key_pem = PemFormatter::key_to_pem(parsed, key_output)?;
+            writer.write(&key_path, &private_key_pem, FileKind::Key, "Private Key")?;
+            output.success(&format!("Private key saved to: {}", key_path.display()))?;
+        }
+        if args.encoding.writes_der() {
+            let key_der_path = output_dir.join(args.key_der_filename());
+            let private_key_der = DerFormatter::key_to_der(parsed, key_output)?;
+            writer.write(
+                &key_der_path,
+                &private_key_der,
+                FileKind::Key,
+                "Private Key (DER)",
+            )?;
+            output.success(&format!(
+                "DER private key saved to: {}",
+                key_der_path.display()
+            ))?;
+        }
+    } else {
+        output.info("No private key found in PFX file, skipping key extraction")?;
+    }
 
     // Convert and save certificate
-    progress.extracting_cert();
-    let cert_pem = PemFormatter::certificate_to_pem(&parsed)?;
-    write_file(&cert_path, &cert_pem)?;
-    output.success(&format!("Certificate saved to: {}", cert_path.display()))?;
+    if parsed.has_certificate() {
+        progress.extracting_cert();
+        if args.encoding.writes_pem() {
+            let cert_pem = PemFormatter::certificate_to_pem(parsed)?;
+            writer.write(&cert_path, &cert_pem, FileKind::Certificate, "Certificate")?;
+            output.success(&format!("Certificate saved to: {}", cert_path.display()))?;
+        }
+        if args.encoding.writes_der() {
+            let cert_der_path = output_dir.join(args.cert_der_filename());
+            let cert_der = DerFormatter::certificate_to_der(parsed)?;
+            writer.write(
+                &cert_der_path,
+                &cert_der,
+                FileKind::Certificate,
+                "Certificate (DER)",
+            )?;
+            output.success(&format!(
+                "DER certificate saved to: {}",
+                cert_der_path.display()
+            ))?;
+        }
+    } else {
+        output.info("No certificate matches a private key, skipping certificate extraction")?;
+    }
 
-    // Handle certificate chain if requested
-    if args.chain && parsed.has_chain() {
+    // Handle certificate chain if requested, or if it is all the file contains
+    if (args.chain || !parsed.has_certificate()) && parsed.has_chain() {
         progress.extracting_chain(parsed.chain_length());
-        save_certificate_chain(&parsed, output_dir, &mut output)?;
+        save_certificate_chain(parsed, output_dir, args.encoding, output, writer)?;
     }
 
     // Create combined file if requested
     if args.combined {
         progress.writing_files();
-        let combined_pem = PemFormatter::combined_to_pem(&parsed, args.chain)?;
-        write_file(&combined_path, &combined_pem)?;
+        let combined_pem = PemFormatter::combined_to_pem_with_key(parsed, args.chain, key_output)?;
+        writer.write(&combined_path, &combined_pem, FileKind::Key, "Combined PEM")?;
         output.success(&format!(
             "Combined PEM saved to: {}",
             combined_path.display()
         ))?;
     }
 
-    // Complete the process
-    progress.complete();
+    Ok(())
+}
 
-    // Print the beautiful summary
-    output.print_summary(&args, &parsed)?;
+/// Write the Kubernetes TLS Secret manifest for an identity
+fn save_secret(
+    args: &Args,
+    parsed: &ParsedPfx,
+    output_dir: &Path,
+    secret_name: &str,
+    key_output: &KeyOutput,
+    output: &mut OutputHandler,
+    writer: &mut FileWriter,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let manifest = render_secret(args, parsed, secret_name, key_output)?;
+    let manifest_path = output_dir.join(args.kubernetes.manifest_filename(secret_name));
+    writer.write(
+        &manifest_path,
+        manifest.as_bytes(),
+        FileKind::Key,
+        "Kubernetes Secret",
+    )?;
+    output.success(&format!(
+        "Kubernetes Secret saved to: {}",
+        manifest_path.display()
+    ))?;
+
+    Ok(())
+}
+
+/// Get the Secret name for an identity, suffixed with its alias when a PFX holds several
+fn secret_name(args: &Args, identity: Option<&str>) -> Option<String> {
+    let name = args.kubernetes.secret_name.as_deref()?;
+    let suffix = identity.map(dns_label).unwrap_or_default();
+    if suffix.is_empty() {
+        Some(name.to_string())
+    } else {
+        Some(format!("{name}-{suffix}"))
+    }
+}
+
+/// Render the Kubernetes TLS Secret manifest for an identity
+fn render_secret(
+    args: &Args,
+    parsed: &ParsedPfx,
+    name: &str,
+    key_output: &KeyOutput,
+) -> Result<String, ConversionError> {
+    let secret = TlsSecret::from_parsed(
+        parsed,
+        name,
+        args.kubernetes.namespace.as_deref(),
+        &args.kubernetes.labels,
+        key_output,
+    )?;
+    Ok(secret.render(args.kubernetes.format))
+}
+
+/// Select the PEM blocks to stream to stdout
+fn stdout_pem(
+    parsed: &ParsedPfx,
+    part: StdoutPart,
+    include_chain: bool,
+    key_output: &KeyOutput,
+) -> Result<Vec<u8>, ConversionError> {
+    match part {
+        StdoutPart::Key => PemFormatter::key_to_pem(parsed, key_output),
+        StdoutPart::Cert => PemFormatter::certificate_to_pem(parsed),
+        StdoutPart::Chain => PemFormatter::chain_to_pem(parsed),
+        StdoutPart::Combined => {
+            PemFormatter::combined_to_pem_with_key(parsed, include_chain, key_output)
+        }
+        // Validation guarantees a Secret name, so this arm is only a fallback
+        StdoutPart::Secret => Err(ConversionError::InvalidOption(
+            "--stdout secret needs a Secret name from --k8s-secret".to_string(),
+        )),
+    }
+}
+
+/// Verify every identity's certificate chain, showing the outcome of each
+///
+/// An identity without a certificate cannot be verified, which fails the run
+/// like an untrusted chain.
+fn verify_identities(
+    identities: &[PfxIdentity],
+    trust_store: &TrustStore,
+    output: &mut OutputHandler,
+) -> Result<Vec<Option<Verification>>, Box<dyn std::error::Error>> {
+    let store = trust_store.load()?;
+    let mut verifications = Vec::new();
+    for identity in identities {
+        if !identity.contents.has_certificate() {
+            let name = identity.friendly_name.as_deref().unwrap_or("the PFX file");
+            return Err(Box::new(ConversionError::Verification(format!(
+                "{name} has no certificate to verify"
+            ))));
+        }
+        let name = identity.friendly_name.as_deref().unwrap_or("certificate");
+        output.status(&format!("Verifying '{name}' against {trust_store}"))?;
+
+        let verification = identity.contents.verify(&store)?;
+        output.print_verification(&verification)?;
+        verifications.push(Some(verification));
+    }
+    Ok(verifications)
+}
+
+/// Describe the first certificate chain that did not verify, if any
+fn untrusted_error(
+    identities: &[PfxIdentity],
+    verifications: &[Option<Verification>],
+    name_format: NameFormat,
+) -> Option<ConversionError> {
+    identities
+        .iter()
+        .zip(verifications)
+        .find_map(|(identity, verification)| {
+            let verification = verification.as_ref()?;
+            let reason = verification.failure.as_ref()?;
+            let subject = verification
+                .failure_depth
+                .and_then(|depth| verification.steps.get(depth))
+                .map(|step| step.subject.format(name_format))
+                .unwrap_or_else(|| {
+                    identity
+                        .friendly_name
+                        .clone()
+                        .unwrap_or_else(|| "certificate".to_string())
+                });
+            Some(ConversionError::Verification(format!(
+                "{reason} ({subject})"
+            )))
+        })
+}
+
+/// Describe the lint errors that fail a `--strict` conversion, if any
+fn lint_error(findings: &[LintFinding]) -> Option<ConversionError> {
+    let errors: Vec<LintFinding> = findings
+        .iter()
+        .filter(|finding| finding.severity == Severity::Error)
+        .cloned()
+        .collect();
+    let rules = rule_names(&errors).join(", ");
+    match errors.len() {
+        0 => None,
+        1 => Some(ConversionError::Policy(format!(
+            "the security lint found 1 error ({rules})"
+        ))),
+        count => Some(ConversionError::Policy(format!(
+            "the security lint found {count} errors ({rules})"
+        ))),
+    }
+}
+
+/// Names of the rules behind some findings, each listed once
+fn rule_names(findings: &[LintFinding]) -> Vec<&str> {
+    let mut rules: Vec<&str> = Vec::new();
+    for finding in findings {
+        if !rules.contains(&finding.rule.as_str()) {
+            rules.push(&finding.rule);
+        }
+    }
+    rules
+}
+
+/// Replace the Prometheus metrics file in one step, as the textfile collector requires
+fn write_metrics(path: &Path, metrics: &[CertificateMetrics]) -> Result<(), ConversionError> {
+    let mut writer = FileWriter::default().overwrite(true);
+    writer.write(
+        path,
+        prometheus_metrics(metrics).as_bytes(),
+        FileKind::Certificate,
+        "Prometheus metrics",
+    )?;
+    writer.commit();
+    Ok(())
+}
+
+/// Describe the outcome of chain ordering as `(is_warning, message)` pairs
+fn chain_report_messages(report: &ChainReport, name_format: NameFormat) -> Vec<(bool, String)> {
+    let subject = |cert: &X509| {
+        CertificateInfo::from_x509(cert)
+            .subject_name
+            .format(name_format)
+    };
+    let mut messages = Vec::new();
+    if report.reordered {
+        messages.push((
+            false,
+            "Certificate chain reordered from leaf to root".to_string(),
+        ));
+    }
+    for cert in &report.duplicates {
+        messages.push((
+            true,
+            format!(
+                "Duplicate certificate removed from chain: {}",
+                subject(cert)
+            ),
+        ));
+    }
+    for cert in &report.unrelated {
+        messages.push((
+            true,
+            format!(
+                "Chain certificate does not belong to the certificate path: {}",
+                subject(cert)
+            ),
+        ));
+    }
+    if let Some(ref root) = report.omitted_root {
+        messages.push((
+            false,
+            format!("Self-signed root omitted from chain: {}", subject(root)),
+        ));
+    }
+    messages
+}
+
+/// Pick a unique, filesystem-safe subdirectory name for an identity
+fn identity_dir_name(identity: &PfxIdentity, index: usize, used: &[String]) -> String {
+    let name = safe_dir_name(identity.friendly_name.as_deref().unwrap_or_default());
+
+    if name.is_empty() || used.contains(&name) {
+        format!("identity_{}", index + 1)
+    } else {
+        name
+    }
+}
+
+/// Replace characters that are unsafe in a directory name and trim surrounding dots
+fn safe_dir_name(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    name.trim_matches('.').to_string()
+}
+
+/// Read PFX data from a file, or from stdin for `-`
+fn read_pfx(
+    path: &str,
+    password: &PasswordArgs,
+    progress: &ProgressReporter,
+) -> Result<Vec<u8>, ConversionError> {
+    password.check_input(path).map_err(|e| {
+        progress.error(&format!("Validation failed: {e}"));
+        e
+    })?;
+    let pfx_data = if path == "-" {
+        PfxParser::read_reader(io::stdin().lock())
+    } else {
+        PfxParser::read_file(Path::new(path))
+    };
+    pfx_data.map_err(|e| {
+        progress.error(&format!("Failed to read PFX: {e}"));
+        e
+    })
+}
+
+/// Parse every identity from PFX data, prompting for the password when needed
+fn parse_pfx(
+    pfx_data: &[u8],
+    password: &PasswordArgs,
+    legacy: bool,
+    progress: &ProgressReporter,
+) -> Result<PfxContents, ConversionError> {
+    let password = password.resolve().map_err(|e| {
+        progress.error(&format!("Failed to read password: {e}"));
+        e
+    })?;
+
+    match read_identities(pfx_data, password.as_deref().unwrap_or_default(), legacy) {
+        // Prompt for the password only when none was given and the file needs one
+        Err(ConversionError::Authentication(_)) if password.is_none() && can_prompt() => {
+            let prompted = progress.suspend(|| prompt_password("PFX password: "))?;
+            read_identities(pfx_data, &prompted, legacy)
+        }
+        result => result,
+    }
+    .map_err(|e| {
+        progress.error(&format!("Failed to parse PFX: {e}"));
+        e
+    })
+}
+
+/// The identities read from a PFX file, and how they were read
+struct PfxContents {
+    identities: Vec<PfxIdentity>,
+    /// Why only the primary identity was extracted, if the SafeBags could not be listed
+    incomplete: Option<String>,
+    /// Whether decryption needed OpenSSL's legacy provider
+    used_legacy_provider: bool,
+}
+
+/// Parse every identity of a PFX file
+///
+/// Legacy encrypted files are retried with the legacy provider when allowed.
+/// Files whose SafeBags cannot be listed fall back to their primary identity.
+fn read_identities(
+    pfx_data: &[u8],
+    password: &str,
+    legacy: bool,
+) -> Result<PfxContents, ConversionError> {
+    let mut used_legacy_provider = false;
+    let identities = match PfxParser::parse_identities(pfx_data, password) {
+        Err(ConversionError::UnsupportedAlgorithm(_)) if legacy => {
+            PfxParser::enable_legacy_provider()?;
+            used_legacy_provider = true;
+            PfxParser::parse_identities(pfx_data, password)
+        }
+        result => result,
+    };
+
+    let (identities, incomplete) = match identities {
+        Ok(identities) => (identities, None),
+        Err(e @ ConversionError::IncompleteIdentities(_)) => {
+            let primary = PfxIdentity {
+                friendly_name: None,
+                contents: PfxParser::parse_bytes(pfx_data, password)?,
+            };
+            (
+                vec![primary],
+                Some(format!("{e}; only the primary identity was extracted")),
+            )
+        }
+        Err(e) => return Err(e),
+    };
+    Ok(PfxContents {
+        identities,
+        incomplete,
+        used_legacy_provider,
+    })
+}
+
+/// Show full X.509 details of every certificate in a PFX file, writing no files
+pub fn inspect_pfx(args: InspectArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let output_config = OutputConfig {
+        format: args.output_format,
+        name_format: args.name_format,
+        time_zone: args.time_zone,
+        ..OutputConfig::new(args.verbose)
+    };
+    let mut output = OutputHandler::new(output_config.clone());
+    let progress = ProgressReporter::new(&output_config);
+
+    output.info(&format!("Input file: {}", args.pfx))?;
+    progress.reading_file(&args.pfx);
+    let pfx_data = read_pfx(&args.pfx, &args.password, &progress)?;
+    let input_format = InputFormat::detect(&pfx_data);
+    output.info(&format!("Detected input format: {input_format}"))?;
+
+    progress.parsing();
+    let PfxContents {
+        identities,
+        incomplete,
+        ..
+    } = parse_pfx(&pfx_data, &args.password, args.legacy, &progress)?;
+    progress.finish();
+    if let Some(ref warning) = incomplete {
+        output.warning(warning)?;
+    }
+
+    if output.is_json() {
+        output.print_json(&inspection_json(
+            &args.pfx,
+            &input_format.to_string(),
+            &identities,
+        ))?;
+    } else {
+        output.print_inspection(&identities)?;
+    }
+
+    Ok(())
+}
+
+/// Check the expiry of every certificate in a PFX file, printing a monitoring status line
+///
+/// Files that cannot be read or parsed are reported as UNKNOWN instead of failing,
+/// so the returned status always maps to a monitoring plugin exit code.
+pub fn check_pfx(args: &CheckArgs) -> ExpiryStatus {
+    match check_expiry(args) {
+        Ok(status) => status,
+        Err(e) => {
+            println!("PFX {} - {e}", ExpiryStatus::Unknown);
+            ExpiryStatus::Unknown
+        }
+    }
+}
+
+fn check_expiry(args: &CheckArgs) -> Result<ExpiryStatus, ConversionError> {
+    let thresholds = args.thresholds()?;
+    // Checks run unattended (cron, monitoring agents), so no progress bar is drawn
+    let progress = ProgressReporter::new(&OutputConfig {
+        interactive: false,
+        ..OutputConfig::new(args.verbose)
+    });
+    let pfx_data = read_pfx(&args.pfx, &args.password, &progress)?;
+    let PfxContents {
+        identities,
+        incomplete,
+        ..
+    } = parse_pfx(&pfx_data, &args.password, args.legacy, &progress)?;
+    // Standard output is the plugin status line, so the warning goes to stderr
+    if let Some(warning) = incomplete {
+        eprintln!("Warning: {warning}");
+    }
+
+    let mut expiries = Vec::new();
+    for identity in &identities {
+        expiries.extend(identity.contents.expiries());
+    }
+    if let Some(ref path) = args.metrics_file {
+        let metrics: Vec<_> = identities
+            .iter()
+            .filter_map(|identity| CertificateMetrics::new(&args.pfx, &identity.contents, None))
+            .collect();
+        write_metrics(path, &metrics)?;
+    }
+
+    let (status, first) = thresholds.evaluate(&expiries);
+    let first =
+        first.ok_or_else(|| ConversionError::MissingComponent("certificate".to_string()))?;
+
+    // Performance data uses the plugin range syntax: alert below the threshold
+    println!(
+        "PFX {status} - {} | days_left={};{}:;{}:",
+        first.describe(),
+        // Undetermined performance data is written as U
+        first
+            .days_left()
+            .map_or_else(|| "U".to_string(), |days| days.to_string()),
+        thresholds.warn_days,
+        thresholds.critical_days
+    );
+    if args.verbose {
+        for expiry in &expiries {
+            println!(
+                "{}: {}",
+                thresholds.expiry_status(expiry),
+                expiry.describe()
+            );
+        }
+    }
+
+    Ok(status)
+}
+
+/// Pack PEM key, certificate and chain files into a PFX/P12 file
+pub fn pack_pem_to_pfx(args: PackArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let output_config = OutputConfig::new(args.verbose);
+    let mut output = OutputHandler::new(output_config);
+
+    output.info("Starting PEM to PFX packing...")?;
+    args.validate()?;
+
+    output.info(&format!("Private key: {}", args.key))?;
+    output.info(&format!("Certificate: {}", args.cert))?;
+    if let Some(ref chain) = args.chain {
+        output.info(&format!("Certificate chain: {chain}"))?;
+    }
+
+    output.status("Packing PEM files into PFX format...")?;
+    let mut parsed = PemParser::parse_files(&args.key, &args.cert, args.chain.as_ref())?;
+    parsed.check_key_match(false)?;
+    for (is_warning, message) in
+        chain_report_messages(&parsed.order_chain(false), NameFormat::default())
+    {
+        if is_warning {
+            output.warning(&message)?;
+        } else {
+            output.info(&message)?;
+        }
+    }
+
+    if parsed.has_chain() {
+        output.info(&format!(
+            "Including {} additional certificates in chain",
+            parsed.chain_length()
+        ))?;
+    }
+
+    let password = match args.password.resolve()? {
+        Some(password) => password,
+        None if can_prompt() => prompt_new_password("Password for the new PFX file: ")?,
+        None => String::new(),
+    };
+    if password.is_empty() {
+        output.info("No password given, the PFX file will not be password-protected")?;
+    }
+
+    let pfx_data = PfxFormatter::to_pfx(&parsed, args.name.as_deref(), &password)?;
+
+    let out_path = Path::new(args.output_path());
+    if let Some(parent) = out_path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)
+            .map_err(|e| ConversionError::DirectoryCreation(parent.display().to_string(), e))?;
+    }
+    FileWriter::default()
+        .overwrite(args.force)
+        .write(out_path, &pfx_data, FileKind::Key, "PFX")?;
+    output.success(&format!("PFX file saved to: {}", out_path.display()))?;
 
     Ok(())
 }
 
 /// Save certificate chain files
+///
+/// PEM output gets a bundle of the whole chain plus one file per certificate;
+/// DER output gets one `.cer` file per chain certificate.
 fn save_certificate_chain(
-    parsed: &crate::openssl::ParsedPfx,
-    output_dir: &str,
+    parsed: &ParsedPfx,
+    output_dir: &Path,
+    encoding: OutputEncoding,
     output: &mut OutputHandler,
+    writer: &mut FileWriter,
 ) -> Result<(), ConversionError> {
-    // Save complete chain in one file
-    let chain_pem = PemFormatter::chain_to_pem(parsed)?;
-    let chain_path = Path::new(output_dir).join("certificate_chain.pem");
-    write_file(&chain_path, &chain_pem)?;
-    output
-        .success(&format!(
-            "Certificate chain saved to: {}",
-            chain_path.display()
-        ))
-        .map_err(|e| ConversionError::FileWrite("output".to_string(), e))?;
-
-    // Save individual chain certificates
-    let chain_certs_pem = PemFormatter::chain_certs_to_pem(parsed)?;
-    for (i, cert_pem) in chain_certs_pem.iter().enumerate() {
-        let cert_path = Path::new(output_dir).join(format!("chain_cert_{}.pem", i + 1));
-        write_file(&cert_path, cert_pem)?;
+    if encoding.writes_pem() {
+        // Save complete chain in one file
+        let chain_pem = PemFormatter::chain_to_pem(parsed)?;
+        let chain_path = output_dir.join("certificate_chain.pem");
+        writer.write(
+            &chain_path,
+            &chain_pem,
+            FileKind::Certificate,
+            "Certificate Chain",
+        )?;
+        output
+            .success(&format!(
+                "Certificate chain saved to: {}",
+                chain_path.display()
+            ))
+            .map_err(|e| ConversionError::FileWrite("output".to_string(), e))?;
+
+        // Save individual chain certificates
+        let chain_certs_pem = PemFormatter::chain_certs_to_pem(parsed)?;
+        save_chain_certs(&chain_certs_pem, output_dir, "pem", "", output, writer)?;
+    }
+
+    if encoding.writes_der() {
+        let chain_certs_der = DerFormatter::chain_certs_to_der(parsed)?;
+        save_chain_certs(
+            &chain_certs_der,
+            output_dir,
+            "cer",
+            " (DER)",
+            output,
+            writer,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Save each chain certificate to its own numbered file
+fn save_chain_certs(
+    certs: &[Vec<u8>],
+    output_dir: &Path,
+    extension: &str,
+    label_suffix: &str,
+    output: &mut OutputHandler,
+    writer: &mut FileWriter,
+) -> Result<(), ConversionError> {
+    for (i, cert_data) in certs.iter().enumerate() {
+        let cert_path = output_dir.join(format!("chain_cert_{}.{extension}", i + 1));
+        writer.write(
+            &cert_path,
+            cert_data,
+            FileKind::Certificate,
+            &format!("Chain Cert {}{label_suffix}", i + 1),
+        )?;
         output
             .info(&format!(
                 "Chain certificate {} saved to: {}",
@@ -142,9 +1218,3 @@ fn save_certificate_chain(
 
     Ok(())
 }
-
-/// Write data to a file with proper error handling
-fn write_file<P: AsRef<Path>>(path: P, data: &[u8]) -> Result<(), ConversionError> {
-    let path = path.as_ref();
-    fs::write(path, data).map_err(|e| ConversionError::FileWrite(path.display().to_string(), e))
-}
@@ -8,14 +8,34 @@ pub enum ConversionError {
     FileRead(String, std::io::Error),
     /// Invalid PFX/P12 file format
     InvalidFormat(String),
-    /// Invalid file extension (not .pfx or .p12)
-    InvalidFileExtension(String),
+    /// Invalid PEM input (key, certificate or chain)
+    InvalidPem(String),
+    /// A required component (private key or certificate) is missing
+    MissingComponent(String),
     /// Wrong password or password required
     Authentication(String),
+    /// Password could not be obtained from the requested source
+    PasswordInput(String),
+    /// A command-line option has an invalid value
+    InvalidOption(String),
+    /// PFX uses an encryption algorithm that is not available (e.g. legacy RC2)
+    UnsupportedAlgorithm(String),
+    /// The PFX parsed, but its SafeBags could not be listed to find every identity
+    IncompleteIdentities(String),
+    /// The private key does not belong to the certificate
+    KeyMismatch(String),
+    /// The certificate does not chain to a trusted root
+    Verification(String),
+    /// The key or certificates violate the security policy
+    Policy(String),
     /// Failed to create output directory
     DirectoryCreation(String, std::io::Error),
+    /// Output file exists and overwriting was not allowed
+    FileExists(String),
     /// Failed to write output file
     FileWrite(String, std::io::Error),
+    /// Some inputs of a batch conversion failed: (failed, total)
+    BatchFailed(usize, usize),
     /// OpenSSL error during conversion
     Ssl(openssl::error::ErrorStack),
 }
@@ -24,26 +44,59 @@ impl fmt::Display for ConversionError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             ConversionError::FileNotFound(path) => {
-                write!(f, "PFX file not found: {path}")
+                write!(f, "File not found: {path}")
             }
             ConversionError::FileRead(path, err) => {
-                write!(f, "Failed to read PFX file '{path}': {err}")
+                write!(f, "Failed to read file '{path}': {err}")
             }
             ConversionError::InvalidFormat(msg) => {
                 write!(f, "Invalid PFX file format: {msg}")
             }
-            ConversionError::InvalidFileExtension(ext) => {
-                write!(f, "Invalid file extension: '{ext}'. Expected .pfx or .p12",)
+            ConversionError::InvalidPem(msg) => {
+                write!(f, "Invalid PEM data: {msg}")
+            }
+            ConversionError::MissingComponent(what) => {
+                write!(f, "Missing {what}: the PFX file does not contain one")
             }
             ConversionError::Authentication(msg) => {
                 write!(f, "Authentication failed: {msg}")
             }
+            ConversionError::PasswordInput(msg) => {
+                write!(f, "Password input error: {msg}")
+            }
+            ConversionError::InvalidOption(msg) => {
+                write!(f, "Invalid option: {msg}")
+            }
+            ConversionError::UnsupportedAlgorithm(msg) => {
+                write!(f, "Unsupported encryption algorithm: {msg}")
+            }
+            ConversionError::IncompleteIdentities(msg) => {
+                write!(f, "Could not list every identity in the PFX file: {msg}")
+            }
+            ConversionError::KeyMismatch(msg) => {
+                write!(f, "Private key does not match the certificate: {msg}")
+            }
+            ConversionError::Verification(msg) => {
+                write!(f, "Certificate verification failed: {msg}")
+            }
+            ConversionError::Policy(msg) => {
+                write!(f, "Policy check failed: {msg}")
+            }
             ConversionError::DirectoryCreation(path, err) => {
                 write!(f, "Failed to create output directory '{path}': {err}")
             }
+            ConversionError::FileExists(path) => {
+                write!(
+                    f,
+                    "Output file '{path}' already exists; use --force to overwrite it"
+                )
+            }
             ConversionError::FileWrite(path, err) => {
                 write!(f, "Failed to write file '{path}': {err}")
             }
+            ConversionError::BatchFailed(failed, total) => {
+                write!(f, "{failed} of {total} PFX files could not be converted")
+            }
             ConversionError::Ssl(err) => {
                 write!(f, "SSL/TLS error: {err}")
             }
@@ -0,0 +1,234 @@
+use crate::error::ConversionError;
+use crate::openssl::{KeyOutput, ParsedPfx, PemFormatter};
+use openssl::base64;
+use serde::{Serialize, Serializer};
+
+/// Output format of a Kubernetes manifest
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ManifestFormat {
+    /// YAML, as used with `kubectl apply -f`
+    #[default]
+    Yaml,
+    /// JSON
+    Json,
+}
+
+impl ManifestFormat {
+    /// File extension for manifests in this format
+    pub fn extension(self) -> &'static str {
+        match self {
+            ManifestFormat::Yaml => "yaml",
+            ManifestFormat::Json => "json",
+        }
+    }
+}
+
+/// A `kubernetes.io/tls` Secret holding a certificate, its chain and private key
+#[derive(Debug, Clone)]
+pub struct TlsSecret {
+    /// Secret name (a DNS subdomain)
+    pub name: String,
+    /// Namespace, or the namespace of the current kubectl context when absent
+    pub namespace: Option<String>,
+    /// Labels in the order given
+    pub labels: Vec<(String, String)>,
+    /// PEM of the leaf certificate followed by the chain
+    pub tls_crt: Vec<u8>,
+    /// PEM of the private key
+    pub tls_key: Vec<u8>,
+    /// PEM of the CA certificates, when the PFX has a chain
+    pub ca_crt: Option<Vec<u8>>,
+}
+
+impl TlsSecret {
+    /// Build a TLS Secret from a parsed PFX
+    ///
+    /// `tls.crt` holds the leaf and chain, `tls.key` the unencrypted key in the
+    /// requested format, and `ca.crt` the chain certificates.
+    pub fn from_parsed(
+        parsed: &ParsedPfx,
+        name: &str,
+        namespace: Option<&str>,
+        labels: &[(String, String)],
+        key_output: &KeyOutput,
+    ) -> Result<Self, ConversionError> {
+        validate_name("Secret name", name)?;
+        if let Some(namespace) = namespace {
+            validate_name("Namespace", namespace)?;
+        }
+        for (key, value) in labels {
+            validate_label(key, value)?;
+        }
+        if key_output.encryption.is_some() {
+            return Err(ConversionError::InvalidOption(
+                "Kubernetes TLS Secrets need an unencrypted private key".to_string(),
+            ));
+        }
+
+        parsed.require_certificate()?;
+        let ca_crt = if parsed.has_chain() {
+            Some(PemFormatter::chain_certs_to_pem(parsed)?.concat())
+        } else {
+            None
+        };
+
+        Ok(Self {
+            name: name.to_string(),
+            namespace: namespace.map(str::to_string),
+            labels: labels.to_vec(),
+            tls_crt: PemFormatter::chain_to_pem(parsed)?,
+            tls_key: PemFormatter::key_to_pem(parsed, key_output)?,
+            ca_crt,
+        })
+    }
+
+    /// Render the Secret in the given format
+    pub fn render(&self, format: ManifestFormat) -> String {
+        match format {
+            ManifestFormat::Yaml => self.to_yaml(),
+            ManifestFormat::Json => self.to_json(),
+        }
+    }
+
+    /// Render the Secret as a YAML manifest
+    pub fn to_yaml(&self) -> String {
+        serde_yaml_ng::to_string(&self.manifest()).expect("Secret manifests serialize to YAML")
+    }
+
+    /// Render the Secret as a JSON manifest
+    pub fn to_json(&self) -> String {
+        let mut json = serde_json::to_string_pretty(&self.manifest())
+            .expect("Secret manifests serialize to JSON");
+        json.push('\n');
+        json
+    }
+
+    /// The manifest document, in the field order of `kubectl get -o yaml`
+    fn manifest(&self) -> SecretManifest<'_> {
+        SecretManifest {
+            api_version: "v1",
+            kind: "Secret",
+            metadata: SecretMetadata {
+                name: &self.name,
+                namespace: self.namespace.as_deref(),
+                labels: &self.labels,
+            },
+            secret_type: "kubernetes.io/tls",
+            data: SecretData {
+                tls_crt: base64::encode_block(&self.tls_crt),
+                tls_key: base64::encode_block(&self.tls_key),
+                ca_crt: self.ca_crt.as_deref().map(base64::encode_block),
+            },
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SecretManifest<'a> {
+    api_version: &'static str,
+    kind: &'static str,
+    metadata: SecretMetadata<'a>,
+    #[serde(rename = "type")]
+    secret_type: &'static str,
+    data: SecretData,
+}
+
+#[derive(Serialize)]
+struct SecretMetadata<'a> {
+    name: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    namespace: Option<&'a str>,
+    #[serde(
+        skip_serializing_if = "<[_]>::is_empty",
+        serialize_with = "serialize_labels"
+    )]
+    labels: &'a [(String, String)],
+}
+
+#[derive(Serialize)]
+struct SecretData {
+    #[serde(rename = "tls.crt")]
+    tls_crt: String,
+    #[serde(rename = "tls.key")]
+    tls_key: String,
+    #[serde(rename = "ca.crt", skip_serializing_if = "Option::is_none")]
+    ca_crt: Option<String>,
+}
+
+/// Serialize labels as a map, in the order given
+fn serialize_labels<S: Serializer>(
+    labels: &&[(String, String)],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_map(labels.iter().map(|(key, value)| (key, value)))
+}
+
+/// Turn arbitrary text (e.g. a friendly name) into a valid DNS label
+pub fn dns_label(text: &str) -> String {
+    let label: String = text
+        .to_ascii_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    label.trim_matches('-').chars().take(63).collect()
+}
+
+/// Parse a `KEY=VALUE` label argument
+pub fn parse_label(arg: &str) -> Result<(String, String), ConversionError> {
+    let (key, value) = arg.split_once('=').ok_or_else(|| {
+        ConversionError::InvalidOption(format!("Label '{arg}' must have the form KEY=VALUE"))
+    })?;
+    validate_label(key, value)?;
+    Ok((key.to_string(), value.to_string()))
+}
+
+/// Check a name against the Kubernetes DNS subdomain rules (RFC 1123)
+pub fn validate_name(what: &str, name: &str) -> Result<(), ConversionError> {
+    let valid = !name.is_empty()
+        && name.len() <= 253
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '.')
+        && name.starts_with(|c: char| c.is_ascii_alphanumeric())
+        && name.ends_with(|c: char| c.is_ascii_alphanumeric());
+    if valid {
+        Ok(())
+    } else {
+        Err(ConversionError::InvalidOption(format!(
+            "{what} '{name}' is not a valid Kubernetes name \
+             (lowercase letters, digits, '-' and '.', starting and ending alphanumeric)"
+        )))
+    }
+}
+
+/// Check a label key (optionally prefixed) and value against Kubernetes rules
+fn validate_label(key: &str, value: &str) -> Result<(), ConversionError> {
+    let is_segment = |text: &str, allow_empty: bool| {
+        (allow_empty && text.is_empty())
+            || (text.len() <= 63
+                && text
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+                && text.starts_with(|c: char| c.is_ascii_alphanumeric())
+                && text.ends_with(|c: char| c.is_ascii_alphanumeric()))
+    };
+
+    let (prefix, name) = match key.split_once('/') {
+        Some((prefix, name)) => (Some(prefix), name),
+        None => (None, key),
+    };
+    let prefix_valid = prefix.is_none_or(|prefix| validate_name("Label prefix", prefix).is_ok());
+
+    if !prefix_valid || !is_segment(name, false) {
+        return Err(ConversionError::InvalidOption(format!(
+            "Label key '{key}' is not a valid Kubernetes label key"
+        )));
+    }
+    if !is_segment(value, true) {
+        return Err(ConversionError::InvalidOption(format!(
+            "Label value '{value}' is not a valid Kubernetes label value"
+        )));
+    }
+    Ok(())
+}
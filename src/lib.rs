@@ -4,10 +4,19 @@
 //!
 //! ## Features
 //!
-//! - Convert PFX/P12 files to PEM format
+//! - Convert PFX/P12 files to PEM format, one at a time or whole directories in a batch
 //! - Support for password-protected files
 //! - Extract certificate chains
+//! - Extract every identity from PFX files holding several key/certificate pairs
 //! - Generate combined PEM files
+//! - Write private keys as encrypted PKCS#8
+//! - Write PEM or binary DER output
+//! - Render Kubernetes TLS Secret manifests
+//! - Order certificate chains and verify them against a trust store
+//! - Lint keys and certificates for weak material and enforce policy files
+//! - Inspect the full X.509 details of every certificate
+//! - Check certificate expiry with monitoring plugin exit codes
+//! - Pack PEM keys, certificates and chains back into PFX/P12 files
 //! - Pure Rust implementation using OpenSSL bindings
 //!
 //! ## Usage as a Library
@@ -27,9 +36,12 @@
 pub mod cli;
 pub mod converter;
 pub mod error;
+pub mod kubernetes;
 pub mod openssl;
 pub mod output;
+pub mod policy;
+pub mod writer;
 
 // Re-export commonly used types
 pub use error::ConversionError;
-pub use openssl::{ParsedPfx, PemFormatter, PfxParser};
+pub use openssl::{ParsedPfx, PemFormatter, PemParser, PfxFormatter, PfxIdentity, PfxParser};
@@ -1,22 +1,66 @@
-mod cli;
-mod converter;
-mod error;
-mod openssl;
-mod output;
-
-use clap::Parser;
-use cli::Args;
+use clap::error::{ContextKind, ContextValue};
+use clap::{CommandFactory, Parser};
+use forge::cli::{Args, Command};
+use forge::converter;
+use forge::openssl::ExpiryStatus;
+use forge::output::{OutputFormat, failure_json};
+use std::env;
 use std::process;
 
 fn main() {
-    let args = Args::parse();
+    let args = Args::try_parse().unwrap_or_else(|e| {
+        // Monitoring reads exit code 2 as CRITICAL, so `check` usage errors are UNKNOWN
+        if e.use_stderr() && is_check_command(&e) {
+            let _ = e.print();
+            process::exit(ExpiryStatus::Unknown.exit_code());
+        }
+        e.exit()
+    });
+    let (input, json_report) = match args.command {
+        Some(Command::Inspect(ref inspect)) => (
+            inspect.pfx.clone(),
+            inspect.output_format == OutputFormat::Json,
+        ),
+        Some(Command::Pack(_) | Command::Check(_)) => (String::new(), false),
+        None => (
+            args.pfx_path().to_string(),
+            args.output_format == OutputFormat::Json,
+        ),
+    };
 
     if let Err(e) = run(args) {
         eprintln!("Error: {e}");
+        // Automation still gets a parseable report on failure
+        if json_report {
+            print!("{}", failure_json(&input, &e.to_string()));
+        }
         process::exit(1);
     }
 }
 
-fn run(args: Args) -> Result<(), Box<dyn std::error::Error>> {
-    converter::convert_pfx_to_pem(args)
+/// Check whether a usage error belongs to the `check` subcommand
+///
+/// Options given before the subcommand are reported as a conflict naming it;
+/// otherwise a second, lenient parse finds the subcommand even when options
+/// after it are invalid.
+fn is_check_command(e: &clap::Error) -> bool {
+    if let Some(ContextValue::String(subcommand)) = e.get(ContextKind::InvalidSubcommand) {
+        return subcommand == "check";
+    }
+    Args::command()
+        .ignore_errors(true)
+        .try_get_matches_from(env::args_os())
+        .is_ok_and(|matches| matches.subcommand_name() == Some("check"))
+}
+
+fn run(mut args: Args) -> Result<(), Box<dyn std::error::Error>> {
+    match args.command.take() {
+        Some(Command::Pack(pack_args)) => converter::pack_pem_to_pfx(pack_args),
+        Some(Command::Inspect(inspect_args)) => converter::inspect_pfx(inspect_args),
+        // Monitoring expects the status as the exit code, even for OK
+        Some(Command::Check(check_args)) => {
+            process::exit(converter::check_pfx(&check_args).exit_code())
+        }
+        None => converter::convert_pfx_to_pem(args),
+    }
 }
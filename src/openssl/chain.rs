@@ -0,0 +1,89 @@
+use crate::openssl::ParsedPfx;
+use openssl::x509::{X509, X509Ref, X509VerifyResult};
+
+/// What happened while ordering a certificate chain
+#[derive(Debug, Clone, Default)]
+pub struct ChainReport {
+    /// The chain order differs from the order stored in the file
+    pub reordered: bool,
+    /// Certificates stored more than once (or repeating the main certificate), removed
+    pub duplicates: Vec<X509>,
+    /// Certificates that are not on the path from the main certificate, kept at the end
+    pub unrelated: Vec<X509>,
+    /// The self-signed root left out of the chain
+    pub omitted_root: Option<X509>,
+}
+
+impl ParsedPfx {
+    /// Order the chain from the main certificate up to the root
+    ///
+    /// Each step picks the certificate that issued the previous one, matching
+    /// issuer/subject names and authority/subject key identifiers. Duplicates
+    /// are dropped and certificates off the path are kept after it. Without a
+    /// main certificate (e.g. CA bundles) only duplicates are removed.
+    pub fn order_chain(&mut self, omit_root: bool) -> ChainReport {
+        let mut report = ChainReport::default();
+        // Drop duplicates, including copies of the main certificate
+        let mut seen: Vec<Vec<u8>> = self
+            .certificate
+            .iter()
+            .filter_map(|c| c.to_der().ok())
+            .collect();
+        let mut remaining = Vec::new();
+        for cert in self.chain.drain(..) {
+            let der = cert.to_der().unwrap_or_default();
+            if seen.contains(&der) {
+                report.duplicates.push(cert);
+            } else {
+                seen.push(der);
+                remaining.push(cert);
+            }
+        }
+
+        let original: Vec<Vec<u8>> = remaining.iter().filter_map(|c| c.to_der().ok()).collect();
+
+        let Some(ref leaf) = self.certificate else {
+            self.chain = remaining;
+            return report;
+        };
+
+        let mut path: Vec<X509> = Vec::new();
+        let mut current: &X509Ref = leaf;
+        while !is_self_signed(current) {
+            let Some(index) = remaining.iter().position(|cert| is_issuer(cert, current)) else {
+                break;
+            };
+            path.push(remaining.remove(index));
+            current = path.last().expect("just pushed");
+        }
+
+        if omit_root && path.last().is_some_and(|cert| is_self_signed(cert)) {
+            report.omitted_root = path.pop();
+        }
+
+        report.unrelated = remaining.clone();
+        path.extend(remaining);
+        self.chain = path;
+
+        let ordered: Vec<Vec<u8>> = self.chain.iter().filter_map(|c| c.to_der().ok()).collect();
+        let kept: Vec<Vec<u8>> = original
+            .into_iter()
+            .filter(|der| ordered.contains(der))
+            .collect();
+        report.reordered = ordered != kept;
+        report
+    }
+}
+
+/// Check whether `issuer` issued `cert`
+///
+/// OpenSSL's check compares the issuer name with the candidate's subject and,
+/// when present, the authority key identifier with its subject key identifier.
+pub fn is_issuer(issuer: &X509Ref, cert: &X509Ref) -> bool {
+    issuer.issued(cert) == X509VerifyResult::OK
+}
+
+/// Check whether a certificate is self-issued (a root)
+pub fn is_self_signed(cert: &X509Ref) -> bool {
+    is_issuer(cert, cert)
+}
@@ -0,0 +1,267 @@
+use crate::error::ConversionError;
+use openssl::asn1::Asn1Object;
+use openssl::nid::Nid;
+
+/// DER tag for BOOLEAN
+pub const TAG_BOOLEAN: u8 = 0x01;
+/// DER tag for INTEGER
+pub const TAG_INTEGER: u8 = 0x02;
+/// DER tag for BIT STRING
+pub const TAG_BIT_STRING: u8 = 0x03;
+/// DER tag for NULL
+pub const TAG_NULL: u8 = 0x05;
+/// DER tag for a primitive OCTET STRING
+pub const TAG_OCTET_STRING: u8 = 0x04;
+/// BER tag for a constructed OCTET STRING
+pub const TAG_OCTET_STRING_CONSTRUCTED: u8 = 0x24;
+/// DER tag for OBJECT IDENTIFIER
+pub const TAG_OID: u8 = 0x06;
+/// DER tag for BMPString
+pub const TAG_BMP_STRING: u8 = 0x1e;
+/// DER tag for SEQUENCE
+pub const TAG_SEQUENCE: u8 = 0x30;
+/// DER tag for SET
+pub const TAG_SET: u8 = 0x31;
+/// Context-specific [0], primitive (IMPLICIT OCTET STRING)
+pub const TAG_CONTEXT_0_PRIMITIVE: u8 = 0x80;
+/// Context-specific [0], constructed (EXPLICIT or IMPLICIT constructed)
+pub const TAG_CONTEXT_0: u8 = 0xa0;
+/// Context-specific [3], constructed (e.g. certificate extensions)
+pub const TAG_CONTEXT_3: u8 = 0xa3;
+
+/// A single tag-length-value element
+#[derive(Debug, Clone, Copy)]
+pub struct Tlv<'a> {
+    /// The identifier octet
+    pub tag: u8,
+    /// The content octets
+    pub content: &'a [u8],
+    /// The complete encoding, including tag and length
+    pub raw: &'a [u8],
+}
+
+impl<'a> Tlv<'a> {
+    /// Read the children of a constructed element
+    pub fn reader(&self) -> DerReader<'a> {
+        DerReader::new(self.content)
+    }
+
+    /// Get the octets of an OCTET STRING, joining the segments of a constructed one
+    pub fn octets(&self) -> Result<Vec<u8>, ConversionError> {
+        match self.tag {
+            TAG_OCTET_STRING | TAG_CONTEXT_0_PRIMITIVE => Ok(self.content.to_vec()),
+            TAG_OCTET_STRING_CONSTRUCTED | TAG_CONTEXT_0 => {
+                let mut octets = Vec::new();
+                let mut reader = self.reader();
+                while !reader.is_empty() {
+                    octets.extend(reader.read()?.octets()?);
+                }
+                Ok(octets)
+            }
+            tag => Err(malformed(&format!(
+                "expected OCTET STRING, found tag 0x{tag:02x}"
+            ))),
+        }
+    }
+
+    /// Decode an OBJECT IDENTIFIER into dotted notation
+    pub fn oid(&self) -> Result<String, ConversionError> {
+        if self.tag != TAG_OID || self.content.is_empty() {
+            return Err(malformed("expected OBJECT IDENTIFIER"));
+        }
+
+        let mut arcs = Vec::new();
+        let mut value: u64 = 0;
+        for &byte in self.content {
+            value = (value << 7) | u64::from(byte & 0x7f);
+            if byte & 0x80 == 0 {
+                if arcs.is_empty() {
+                    let first = (value / 40).min(2);
+                    arcs.push(first);
+                    arcs.push(value - first * 40);
+                } else {
+                    arcs.push(value);
+                }
+                value = 0;
+            }
+        }
+
+        Ok(arcs
+            .iter()
+            .map(u64::to_string)
+            .collect::<Vec<_>>()
+            .join("."))
+    }
+
+    /// Decode a small non-negative INTEGER
+    pub fn integer(&self) -> Result<u64, ConversionError> {
+        if self.tag != TAG_INTEGER || self.content.is_empty() || self.content.len() > 8 {
+            return Err(malformed("expected small INTEGER"));
+        }
+        Ok(self
+            .content
+            .iter()
+            .fold(0u64, |acc, &byte| (acc << 8) | u64::from(byte)))
+    }
+
+    /// Decode a BMPString (UTF-16BE)
+    pub fn bmp_string(&self) -> Result<String, ConversionError> {
+        if self.tag != TAG_BMP_STRING || !self.content.len().is_multiple_of(2) {
+            return Err(malformed("expected BMPString"));
+        }
+        let units: Vec<u16> = self
+            .content
+            .chunks_exact(2)
+            .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+            .collect();
+        Ok(String::from_utf16_lossy(&units))
+    }
+}
+
+/// Sequential reader over DER encoded elements
+#[derive(Debug, Clone)]
+pub struct DerReader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> DerReader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    /// Check whether all elements have been read
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Read the next element
+    pub fn read(&mut self) -> Result<Tlv<'a>, ConversionError> {
+        let data = self.data;
+        if data.len() < 2 {
+            return Err(malformed("unexpected end of data"));
+        }
+
+        let tag = data[0];
+        if tag & 0x1f == 0x1f {
+            return Err(malformed("multi-byte tags are not supported"));
+        }
+
+        let (length, header_len) = match data[1] {
+            len if len & 0x80 == 0 => (usize::from(len), 2),
+            0x80 => return Err(malformed("indefinite lengths are not supported")),
+            len => {
+                let count = usize::from(len & 0x7f);
+                if count > std::mem::size_of::<usize>() || data.len() < 2 + count {
+                    return Err(malformed("invalid length encoding"));
+                }
+                let length = data[2..2 + count]
+                    .iter()
+                    .fold(0usize, |acc, &byte| (acc << 8) | usize::from(byte));
+                (length, 2 + count)
+            }
+        };
+
+        let end = header_len
+            .checked_add(length)
+            .filter(|&end| end <= data.len())
+            .ok_or_else(|| malformed("element extends past end of data"))?;
+
+        self.data = &data[end..];
+        Ok(Tlv {
+            tag,
+            content: &data[header_len..end],
+            raw: &data[..end],
+        })
+    }
+
+    /// Read the next element, checking its tag
+    pub fn expect(&mut self, tag: u8) -> Result<Tlv<'a>, ConversionError> {
+        let tlv = self.read()?;
+        if tlv.tag != tag {
+            return Err(malformed(&format!(
+                "expected tag 0x{tag:02x}, found 0x{:02x}",
+                tlv.tag
+            )));
+        }
+        Ok(tlv)
+    }
+
+    /// Read the next element if it has the given tag
+    pub fn read_optional(&mut self, tag: u8) -> Result<Option<Tlv<'a>>, ConversionError> {
+        if self.data.first() == Some(&tag) {
+            self.read().map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+/// Encode a single element from its tag and content octets
+pub fn encode(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut out = vec![tag];
+    let len = content.len();
+    if len < 0x80 {
+        out.push(len as u8);
+    } else {
+        let bytes: Vec<u8> = len
+            .to_be_bytes()
+            .into_iter()
+            .skip_while(|&byte| byte == 0)
+            .collect();
+        out.push(0x80 | bytes.len() as u8);
+        out.extend(bytes);
+    }
+    out.extend_from_slice(content);
+    out
+}
+
+/// Encode a SEQUENCE from already encoded elements
+pub fn encode_sequence(elements: &[Vec<u8>]) -> Vec<u8> {
+    encode(TAG_SEQUENCE, &elements.concat())
+}
+
+/// Encode a non-negative INTEGER
+pub fn encode_integer(value: u64) -> Vec<u8> {
+    let mut bytes: Vec<u8> = value
+        .to_be_bytes()
+        .into_iter()
+        .skip_while(|&byte| byte == 0)
+        .collect();
+    // Keep the value positive when the high bit is set, and encode zero as one octet
+    if bytes.first().is_none_or(|&byte| byte & 0x80 != 0) {
+        bytes.insert(0, 0);
+    }
+    encode(TAG_INTEGER, &bytes)
+}
+
+/// Encode an OBJECT IDENTIFIER from dotted notation
+pub fn encode_oid(oid: &str) -> Vec<u8> {
+    let arcs: Vec<u64> = oid.split('.').filter_map(|arc| arc.parse().ok()).collect();
+    let mut content = Vec::new();
+    let first =
+        arcs.first().copied().unwrap_or_default() * 40 + arcs.get(1).copied().unwrap_or_default();
+    for arc in std::iter::once(first).chain(arcs.iter().skip(2).copied()) {
+        let mut groups = vec![(arc & 0x7f) as u8];
+        let mut rest = arc >> 7;
+        while rest > 0 {
+            groups.push((rest & 0x7f) as u8 | 0x80);
+            rest >>= 7;
+        }
+        content.extend(groups.into_iter().rev());
+    }
+    encode(TAG_OID, &content)
+}
+
+/// Get a readable name for a dotted OID, falling back to the OID itself
+pub fn oid_name(oid: &str) -> String {
+    Asn1Object::from_str(oid)
+        .ok()
+        .map(|obj| obj.nid())
+        .filter(|&nid| nid != Nid::UNDEF)
+        .and_then(|nid| nid.long_name().ok().map(str::to_string))
+        .unwrap_or_else(|| oid.to_string())
+}
+
+fn malformed(msg: &str) -> ConversionError {
+    ConversionError::InvalidFormat(format!("Malformed PKCS#12 structure: {msg}"))
+}
@@ -0,0 +1,286 @@
+use crate::openssl::der::{
+    self, DerReader, TAG_BIT_STRING, TAG_BOOLEAN, TAG_CONTEXT_3, TAG_INTEGER, TAG_OCTET_STRING,
+    TAG_SEQUENCE,
+};
+use crate::openssl::{CertificateInfo, key_type_name};
+use openssl::hash::MessageDigest;
+use openssl::nid::Nid;
+use openssl::pkey::Id;
+use openssl::x509::{GeneralNameRef, X509};
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+const OID_KEY_USAGE: &str = "2.5.29.15";
+const OID_BASIC_CONSTRAINTS: &str = "2.5.29.19";
+const OID_CERTIFICATE_POLICIES: &str = "2.5.29.32";
+const OID_EXTENDED_KEY_USAGE: &str = "2.5.29.37";
+
+/// Key usage bits in the order of RFC 5280
+const KEY_USAGES: [&str; 9] = [
+    "Digital Signature",
+    "Non Repudiation",
+    "Key Encipherment",
+    "Data Encipherment",
+    "Key Agreement",
+    "Certificate Sign",
+    "CRL Sign",
+    "Encipher Only",
+    "Decipher Only",
+];
+
+/// The basicConstraints extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BasicConstraints {
+    /// The certificate belongs to a CA
+    pub ca: bool,
+    /// Maximum number of intermediate CAs below this one
+    pub path_length: Option<u64>,
+}
+
+/// Full X.509 details of a certificate, for inspection
+#[derive(Debug, Clone)]
+pub struct CertificateDetails {
+    /// Subject, issuer, serial number, validity and signature algorithm
+    pub info: CertificateInfo,
+    /// X.509 version (1 to 3)
+    pub version: i32,
+    /// Subject alternative names, e.g. `DNS:example.com` or `IP:192.0.2.1`
+    pub subject_alt_names: Vec<String>,
+    /// Key usages, e.g. "Digital Signature"
+    pub key_usage: Vec<String>,
+    /// Extended key usages, e.g. "TLS Web Server Authentication"
+    pub extended_key_usage: Vec<String>,
+    /// The basicConstraints extension, if present
+    pub basic_constraints: Option<BasicConstraints>,
+    /// Authority key identifier as colon separated hex
+    pub authority_key_id: Option<String>,
+    /// Subject key identifier as colon separated hex
+    pub subject_key_id: Option<String>,
+    /// Public key algorithm, e.g. "RSA" or "EC"
+    pub public_key_algorithm: String,
+    /// Public key size in bits
+    pub public_key_bits: u32,
+    /// Named curve of EC keys, e.g. "prime256v1"
+    pub public_key_curve: Option<String>,
+    /// SHA-1 fingerprint as colon separated hex
+    pub sha1_fingerprint: String,
+    /// SHA-256 fingerprint as colon separated hex
+    pub sha256_fingerprint: String,
+    /// OCSP responder URLs from the Authority Information Access extension
+    pub ocsp_urls: Vec<String>,
+    /// CA issuer URLs from the Authority Information Access extension
+    pub ca_issuer_urls: Vec<String>,
+    /// CRL distribution point URLs
+    pub crl_urls: Vec<String>,
+    /// Certificate policy OIDs, with their names when known
+    pub policies: Vec<String>,
+}
+
+impl CertificateDetails {
+    /// Collect every detail of a certificate
+    pub fn from_x509(cert: &X509) -> Self {
+        let extensions = cert
+            .to_der()
+            .map(|der| read_extensions(&der))
+            .unwrap_or_default();
+        let extension = |oid: &str| {
+            extensions
+                .iter()
+                .find(|(ext_oid, _)| ext_oid == oid)
+                .map(|(_, value)| value.as_slice())
+        };
+
+        let public_key = cert.public_key().ok();
+        let public_key_curve = public_key
+            .as_ref()
+            .filter(|key| key.id() == Id::EC)
+            .and_then(|key| key.ec_key().ok())
+            .and_then(|ec| ec.group().curve_name())
+            .and_then(|nid| nid.short_name().ok())
+            .map(str::to_string);
+
+        let mut ocsp_urls = Vec::new();
+        let mut ca_issuer_urls = Vec::new();
+        for access in cert.authority_info().iter().flatten() {
+            let Some(url) = access.location().uri() else {
+                continue;
+            };
+            match access.method().nid() {
+                Nid::AD_OCSP => ocsp_urls.push(url.to_string()),
+                Nid::AD_CA_ISSUERS => ca_issuer_urls.push(url.to_string()),
+                _ => {}
+            }
+        }
+
+        let crl_urls = cert
+            .crl_distribution_points()
+            .iter()
+            .flatten()
+            .filter_map(|point| point.distpoint()?.fullname())
+            .flatten()
+            .filter_map(|name| name.uri().map(str::to_string))
+            .collect();
+
+        Self {
+            info: CertificateInfo::from_x509(cert),
+            version: cert.version() + 1,
+            subject_alt_names: cert
+                .subject_alt_names()
+                .iter()
+                .flatten()
+                .filter_map(general_name)
+                .collect(),
+            key_usage: extension(OID_KEY_USAGE).map(key_usage).unwrap_or_default(),
+            extended_key_usage: extension(OID_EXTENDED_KEY_USAGE)
+                .map(extended_key_usage)
+                .unwrap_or_default(),
+            basic_constraints: extension(OID_BASIC_CONSTRAINTS).and_then(basic_constraints),
+            authority_key_id: cert.authority_key_id().map(|id| hex(id.as_slice())),
+            subject_key_id: cert.subject_key_id().map(|id| hex(id.as_slice())),
+            public_key_algorithm: public_key
+                .as_ref()
+                .map_or("unknown", |key| key_type_name(key.id()))
+                .to_string(),
+            public_key_bits: public_key.as_ref().map_or(0, |key| key.bits()),
+            public_key_curve,
+            sha1_fingerprint: fingerprint(cert, MessageDigest::sha1()),
+            sha256_fingerprint: fingerprint(cert, MessageDigest::sha256()),
+            ocsp_urls,
+            ca_issuer_urls,
+            crl_urls,
+            policies: extension(OID_CERTIFICATE_POLICIES)
+                .map(policies)
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Read the `(OID, value)` pairs of a certificate's extensions
+fn read_extensions(cert_der: &[u8]) -> Vec<(String, Vec<u8>)> {
+    let read = || -> Option<Vec<(String, Vec<u8>)>> {
+        let certificate = DerReader::new(cert_der).expect(TAG_SEQUENCE).ok()?;
+        let tbs = certificate.reader().expect(TAG_SEQUENCE).ok()?;
+
+        // Extensions are the [3] element at the end of the TBSCertificate
+        let mut fields = tbs.reader();
+        let mut wrapper = None;
+        while !fields.is_empty() {
+            let field = fields.read().ok()?;
+            if field.tag == TAG_CONTEXT_3 {
+                wrapper = Some(field);
+            }
+        }
+        let list = wrapper?.reader().expect(TAG_SEQUENCE).ok()?;
+
+        let mut extensions = Vec::new();
+        let mut reader = list.reader();
+        while !reader.is_empty() {
+            let mut extension = reader.expect(TAG_SEQUENCE).ok()?.reader();
+            let oid = extension.read().ok()?.oid().ok()?;
+            extension.read_optional(TAG_BOOLEAN).ok()?;
+            let value = extension.expect(TAG_OCTET_STRING).ok()?;
+            extensions.push((oid, value.content.to_vec()));
+        }
+        Some(extensions)
+    };
+    read().unwrap_or_default()
+}
+
+/// Decode the keyUsage BIT STRING
+fn key_usage(value: &[u8]) -> Vec<String> {
+    let Ok(bits) = DerReader::new(value).expect(TAG_BIT_STRING) else {
+        return Vec::new();
+    };
+    let bytes = bits.content.get(1..).unwrap_or_default();
+    KEY_USAGES
+        .iter()
+        .enumerate()
+        .filter(|(bit, _)| {
+            bytes
+                .get(bit / 8)
+                .is_some_and(|byte| byte & (0x80 >> (bit % 8)) != 0)
+        })
+        .map(|(_, name)| name.to_string())
+        .collect()
+}
+
+/// Decode the extKeyUsage list of purpose OIDs
+fn extended_key_usage(value: &[u8]) -> Vec<String> {
+    oid_list(value, |element| element.oid().ok())
+        .into_iter()
+        .map(|oid| der::oid_name(&oid))
+        .collect()
+}
+
+/// Decode the certificatePolicies list, keeping each policy OID
+fn policies(value: &[u8]) -> Vec<String> {
+    oid_list(value, |policy| policy.reader().read().ok()?.oid().ok())
+        .into_iter()
+        .map(|oid| match der::oid_name(&oid) {
+            name if name == oid => oid,
+            name => format!("{name} ({oid})"),
+        })
+        .collect()
+}
+
+/// Decode each element of a SEQUENCE with `decode`, skipping those that fail
+fn oid_list(value: &[u8], decode: impl Fn(&der::Tlv) -> Option<String>) -> Vec<String> {
+    let Ok(sequence) = DerReader::new(value).expect(TAG_SEQUENCE) else {
+        return Vec::new();
+    };
+    let mut reader = sequence.reader();
+    let mut items = Vec::new();
+    while let Ok(element) = reader.read() {
+        items.extend(decode(&element));
+    }
+    items
+}
+
+/// Decode basicConstraints: an optional cA flag and path length
+fn basic_constraints(value: &[u8]) -> Option<BasicConstraints> {
+    let sequence = DerReader::new(value).expect(TAG_SEQUENCE).ok()?;
+    let mut reader = sequence.reader();
+    let ca = reader
+        .read_optional(TAG_BOOLEAN)
+        .ok()?
+        .is_some_and(|flag| flag.content.first().is_some_and(|&byte| byte != 0));
+    let path_length = reader
+        .read_optional(TAG_INTEGER)
+        .ok()?
+        .and_then(|length| length.integer().ok());
+    Some(BasicConstraints { ca, path_length })
+}
+
+/// Describe a general name the way OpenSSL prints it, e.g. `DNS:example.com`
+fn general_name(name: &GeneralNameRef) -> Option<String> {
+    if let Some(dns) = name.dnsname() {
+        return Some(format!("DNS:{dns}"));
+    }
+    if let Some(ip) = name.ipaddress() {
+        let address = match ip.len() {
+            4 => Ipv4Addr::from(<[u8; 4]>::try_from(ip).ok()?).to_string(),
+            16 => Ipv6Addr::from(<[u8; 16]>::try_from(ip).ok()?).to_string(),
+            _ => hex(ip),
+        };
+        return Some(format!("IP:{address}"));
+    }
+    if let Some(email) = name.email() {
+        return Some(format!("email:{email}"));
+    }
+    name.uri().map(|uri| format!("URI:{uri}"))
+}
+
+/// Colon separated upper-case hex digest of a certificate
+fn fingerprint(cert: &X509, digest: MessageDigest) -> String {
+    cert.digest(digest)
+        .map(|bytes| hex(&bytes))
+        .unwrap_or_default()
+}
+
+/// Format bytes as colon separated upper-case hex
+fn hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|byte| format!("{byte:02X}"))
+        .collect::<Vec<_>>()
+        .join(":")
+}
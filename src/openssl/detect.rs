@@ -0,0 +1,115 @@
+use crate::error::ConversionError;
+use openssl::base64;
+use openssl::pkcs12::Pkcs12;
+use openssl::pkey::PKey;
+use openssl::x509::X509;
+use std::borrow::Cow;
+use std::fmt;
+
+/// The format of an input file, detected from its content
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InputFormat {
+    /// Binary (DER) PKCS#12
+    Pkcs12Der,
+    /// Base64 encoded PKCS#12, e.g. from Azure Key Vault or Kubernetes secrets
+    Pkcs12Base64,
+    /// PEM data, with the label of the first block (e.g. "CERTIFICATE")
+    Pem(String),
+    /// A single DER encoded X.509 certificate
+    X509Der,
+    /// A DER encoded private key
+    PrivateKeyDer,
+    /// Text that is not PEM or base64 PKCS#12
+    Text,
+    /// Binary data of unknown format
+    Unknown,
+}
+
+impl fmt::Display for InputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InputFormat::Pkcs12Der => write!(f, "PKCS#12 (DER)"),
+            InputFormat::Pkcs12Base64 => write!(f, "PKCS#12 (base64 encoded)"),
+            InputFormat::Pem(label) => write!(f, "PEM ({label})"),
+            InputFormat::X509Der => write!(f, "DER encoded X.509 certificate"),
+            InputFormat::PrivateKeyDer => write!(f, "DER encoded private key"),
+            InputFormat::Text => write!(f, "text"),
+            InputFormat::Unknown => write!(f, "unrecognized binary data"),
+        }
+    }
+}
+
+impl InputFormat {
+    /// Detect the format of input data by sniffing its content
+    pub fn detect(data: &[u8]) -> Self {
+        if Pkcs12::from_der(data).is_ok() {
+            return InputFormat::Pkcs12Der;
+        }
+
+        if let Ok(text) = std::str::from_utf8(data) {
+            if let Some(label) = pem_label(text) {
+                return InputFormat::Pem(label);
+            }
+            if decode_base64(text).is_some_and(|der| Pkcs12::from_der(&der).is_ok()) {
+                return InputFormat::Pkcs12Base64;
+            }
+        }
+
+        if X509::from_der(data).is_ok() {
+            return InputFormat::X509Der;
+        }
+
+        if PKey::private_key_from_der(data).is_ok() {
+            return InputFormat::PrivateKeyDer;
+        }
+
+        let printable = data
+            .iter()
+            .all(|b| b.is_ascii_graphic() || b.is_ascii_whitespace());
+        if printable {
+            InputFormat::Text
+        } else {
+            InputFormat::Unknown
+        }
+    }
+}
+
+/// Get DER PKCS#12 data from raw or base64 encoded input
+///
+/// Any other format is rejected with an error naming what was detected.
+pub fn pkcs12_der(data: &[u8]) -> Result<Cow<'_, [u8]>, ConversionError> {
+    match InputFormat::detect(data) {
+        InputFormat::Pkcs12Der => Ok(Cow::Borrowed(data)),
+        InputFormat::Pkcs12Base64 => std::str::from_utf8(data)
+            .ok()
+            .and_then(decode_base64)
+            .map(Cow::Owned)
+            .ok_or_else(|| {
+                ConversionError::InvalidFormat("Failed to decode base64 PFX data".to_string())
+            }),
+        InputFormat::Pem(label) => Err(ConversionError::InvalidFormat(format!(
+            "Input is PEM ({label}), not a PFX/PKCS#12 file. \
+             Use 'forge pack' to build a PFX from PEM files."
+        ))),
+        other => Err(ConversionError::InvalidFormat(format!(
+            "Input is {other}, not a PFX/PKCS#12 file"
+        ))),
+    }
+}
+
+/// Find the label of the first PEM block, e.g. "CERTIFICATE"
+fn pem_label(text: &str) -> Option<String> {
+    let start = text.find("-----BEGIN ")? + "-----BEGIN ".len();
+    let end = text[start..].find("-----")?;
+    Some(text[start..start + end].to_string())
+}
+
+/// Decode base64 text, ignoring whitespace and line breaks
+fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    let compact: String = text.chars().filter(|c| !c.is_whitespace()).collect();
+    if compact.is_empty() || !compact.len().is_multiple_of(4) {
+        return None;
+    }
+
+    base64::decode_block(&compact).ok()
+}
@@ -0,0 +1,196 @@
+use crate::error::ConversionError;
+use crate::openssl::time::SECONDS_PER_DAY;
+use crate::openssl::{CertificateInfo, ParsedPfx, Timestamp};
+use openssl::x509::X509;
+use std::fmt;
+
+/// Result of an expiry check, ordered from best to worst
+///
+/// The exit codes follow the Nagios plugin convention, so `forge check` can
+/// be used by monitoring systems directly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ExpiryStatus {
+    /// No certificate expires within the warning period
+    Ok,
+    /// A certificate expires within the warning period
+    Warning,
+    /// A certificate expires within the critical period or has expired
+    Critical,
+    /// The file could not be checked
+    Unknown,
+}
+
+impl ExpiryStatus {
+    /// Exit code for monitoring plugins: 0, 1, 2 or 3
+    pub fn exit_code(self) -> i32 {
+        match self {
+            ExpiryStatus::Ok => 0,
+            ExpiryStatus::Warning => 1,
+            ExpiryStatus::Critical => 2,
+            ExpiryStatus::Unknown => 3,
+        }
+    }
+}
+
+impl fmt::Display for ExpiryStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ExpiryStatus::Ok => "OK",
+            ExpiryStatus::Warning => "WARNING",
+            ExpiryStatus::Critical => "CRITICAL",
+            ExpiryStatus::Unknown => "UNKNOWN",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Number of days before expiry that raise a warning or a critical status
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExpiryThresholds {
+    pub warn_days: u32,
+    pub critical_days: u32,
+}
+
+impl Default for ExpiryThresholds {
+    /// Warn 30 days and go critical 7 days before expiry
+    fn default() -> Self {
+        Self {
+            warn_days: 30,
+            critical_days: 7,
+        }
+    }
+}
+
+impl ExpiryThresholds {
+    /// Create thresholds, rejecting a critical period longer than the warning period
+    pub fn new(warn_days: u32, critical_days: u32) -> Result<Self, ConversionError> {
+        if critical_days > warn_days {
+            return Err(ConversionError::InvalidOption(format!(
+                "--critical-days ({critical_days}) must not be greater than --warn-days ({warn_days})"
+            )));
+        }
+        Ok(Self {
+            warn_days,
+            critical_days,
+        })
+    }
+
+    /// Status of a certificate with `days_left` whole days of validity
+    pub fn status(&self, days_left: i64) -> ExpiryStatus {
+        if days_left < i64::from(self.critical_days) {
+            ExpiryStatus::Critical
+        } else if days_left < i64::from(self.warn_days) {
+            ExpiryStatus::Warning
+        } else {
+            ExpiryStatus::Ok
+        }
+    }
+
+    /// Status of one certificate; `Unknown` when its notAfter cannot be read
+    pub fn expiry_status(&self, expiry: &CertificateExpiry) -> ExpiryStatus {
+        expiry
+            .days_left()
+            .map_or(ExpiryStatus::Unknown, |days_left| self.status(days_left))
+    }
+
+    /// Status of the certificate that expires first, which is the worst status
+    ///
+    /// Returns `Unknown` when there are no certificates to check, or with the
+    /// first certificate whose notAfter cannot be read.
+    pub fn evaluate<'a>(
+        &self,
+        expiries: &'a [CertificateExpiry],
+    ) -> (ExpiryStatus, Option<&'a CertificateExpiry>) {
+        // None sorts first, so an unreadable date wins over any real one
+        let first = expiries.iter().min_by_key(|expiry| expiry.seconds_left);
+        let status = first.map_or(ExpiryStatus::Unknown, |expiry| self.expiry_status(expiry));
+        (status, first)
+    }
+}
+
+/// Remaining validity of one certificate
+#[derive(Debug, Clone)]
+pub struct CertificateExpiry {
+    /// Common name of the certificate, or its full subject without one
+    pub name: String,
+    /// The notAfter date, `None` if it cannot be read
+    pub not_after: Option<Timestamp>,
+    /// Seconds until notAfter, negative once expired
+    pub seconds_left: Option<i64>,
+    /// The certificate is a main certificate rather than a chain certificate
+    pub is_leaf: bool,
+}
+
+impl CertificateExpiry {
+    /// Measure the remaining validity of a certificate from now
+    pub fn from_x509(cert: &X509, is_leaf: bool) -> Self {
+        Self::new(&CertificateInfo::from_x509(cert), is_leaf, Timestamp::now())
+    }
+
+    /// Measure the remaining validity of a certificate from a point in time
+    pub fn new(info: &CertificateInfo, is_leaf: bool, now: Timestamp) -> Self {
+        Self {
+            name: info
+                .common_name
+                .clone()
+                .unwrap_or_else(|| info.subject.clone()),
+            not_after: info.not_after,
+            seconds_left: info
+                .not_after
+                .map(|not_after| not_after.unix_seconds() - now.unix_seconds()),
+            is_leaf,
+        }
+    }
+
+    /// Whole days of validity left, negative once expired
+    pub fn days_left(&self) -> Option<i64> {
+        self.seconds_left
+            .map(|seconds| seconds.div_euclid(SECONDS_PER_DAY))
+    }
+
+    /// Describe the remaining validity, e.g. "certificate 'example.com' expires in 12 days"
+    pub fn describe(&self) -> String {
+        let kind = if self.is_leaf {
+            "certificate"
+        } else {
+            "chain certificate"
+        };
+        let when = match (self.seconds_left, self.not_after) {
+            (Some(seconds), Some(not_after)) if seconds < 0 => format!("expired on {not_after}"),
+            (Some(_), Some(not_after)) => format!("{} ({not_after})", self.relative()),
+            _ => "has an unreadable expiry date".to_string(),
+        };
+        format!("{kind} '{}' {when}", self.name)
+    }
+
+    /// Describe the remaining validity relative to now, e.g. "expired 3 days ago"
+    pub fn relative(&self) -> String {
+        let Some(seconds_left) = self.seconds_left else {
+            return "unknown".to_string();
+        };
+        let days = seconds_left.abs() / SECONDS_PER_DAY;
+        match (seconds_left < 0, days) {
+            (false, 0) => "expires in less than a day".to_string(),
+            (false, 1) => "expires in 1 day".to_string(),
+            (false, _) => format!("expires in {days} days"),
+            (true, 0) => "expired less than a day ago".to_string(),
+            (true, 1) => "expired 1 day ago".to_string(),
+            (true, _) => format!("expired {days} days ago"),
+        }
+    }
+}
+
+impl ParsedPfx {
+    /// Remaining validity of the main certificate and every chain certificate
+    pub fn expiries(&self) -> Vec<CertificateExpiry> {
+        let leaf = self
+            .certificate
+            .iter()
+            .map(|cert| CertificateExpiry::from_x509(cert, true));
+        let chain = self
+            .chain
+            .iter()
+            .map(|cert| CertificateExpiry::from_x509(cert, false));
+        leaf.chain(chain).collect()
+    }
+}
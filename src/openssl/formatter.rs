@@ -1,5 +1,8 @@
 use crate::error::ConversionError;
 use crate::openssl::ParsedPfx;
+use crate::openssl::key::KeyOutput;
+use openssl::pkcs12::Pkcs12;
+use openssl::stack::Stack;
 use openssl::x509::X509;
 
 /// Formatter for converting certificates and keys to PEM format
@@ -9,7 +12,7 @@ impl PemFormatter {
     /// Convert private key to PEM format
     pub fn private_key_to_pem(parsed: &ParsedPfx) -> Result<Vec<u8>, ConversionError> {
         let pem_data = parsed
-            .private_key
+            .require_private_key()?
             .private_key_to_pem_pkcs8()
             .map_err(ConversionError::from)?;
 
@@ -23,9 +26,29 @@ impl PemFormatter {
         Ok(pem_data)
     }
 
+    /// Convert private key to PEM in the requested format, encrypting it when configured
+    pub fn key_to_pem(
+        parsed: &ParsedPfx,
+        key_output: &KeyOutput,
+    ) -> Result<Vec<u8>, ConversionError> {
+        let pem_data = key_output.to_pem(parsed.require_private_key()?)?;
+
+        // Validate the generated PEM
+        if !Self::validate_pem(&pem_data) {
+            return Err(ConversionError::InvalidFormat(
+                "Generated private key PEM is invalid".to_string(),
+            ));
+        }
+
+        Ok(pem_data)
+    }
+
     /// Convert certificate to PEM format
     pub fn certificate_to_pem(parsed: &ParsedPfx) -> Result<Vec<u8>, ConversionError> {
-        let pem_data = parsed.certificate.to_pem().map_err(ConversionError::from)?;
+        let pem_data = parsed
+            .require_certificate()?
+            .to_pem()
+            .map_err(ConversionError::from)?;
 
         // Validate the generated PEM
         if !Self::validate_pem(&pem_data) {
@@ -52,8 +75,14 @@ impl PemFormatter {
     }
 
     /// Convert certificate chain to PEM format (all certificates concatenated)
+    ///
+    /// The main certificate comes first when present; certificate-only files
+    /// (e.g. CA bundles) produce just the chain certificates.
     pub fn chain_to_pem(parsed: &ParsedPfx) -> Result<Vec<u8>, ConversionError> {
-        let mut chain_pem = Self::certificate_to_pem(parsed)?;
+        let mut chain_pem = match parsed.certificate {
+            Some(ref cert) => Self::cert_to_pem(cert)?,
+            None => Vec::new(),
+        };
 
         for cert in &parsed.chain {
             let cert_pem = Self::cert_to_pem(cert)?;
@@ -80,7 +109,17 @@ impl PemFormatter {
         parsed: &ParsedPfx,
         include_chain: bool,
     ) -> Result<Vec<u8>, ConversionError> {
-        let mut combined = Self::private_key_to_pem(parsed)?;
+        Self::combined_to_pem_with_key(parsed, include_chain, &KeyOutput::default())
+    }
+
+    /// Create a combined PEM, writing the private key according to `key_output`
+    pub fn combined_to_pem_with_key(
+        parsed: &ParsedPfx,
+        include_chain: bool,
+        key_output: &KeyOutput,
+    ) -> Result<Vec<u8>, ConversionError> {
+        parsed.require_certificate()?;
+        let mut combined = Self::key_to_pem(parsed, key_output)?;
 
         if include_chain && parsed.has_chain() {
             let chain_pem = Self::chain_to_pem(parsed)?;
@@ -122,3 +161,68 @@ impl PemFormatter {
         begin_count == end_count && begin_count > 0
     }
 }
+
+/// Formatter for converting certificates and keys to binary DER format
+///
+/// DER files hold a single object, so there is no combined or bundled chain form.
+pub struct DerFormatter;
+
+impl DerFormatter {
+    /// Convert private key to DER in the requested format, encrypting it when configured
+    pub fn key_to_der(
+        parsed: &ParsedPfx,
+        key_output: &KeyOutput,
+    ) -> Result<Vec<u8>, ConversionError> {
+        key_output.to_der(parsed.require_private_key()?)
+    }
+
+    /// Convert certificate to DER format
+    pub fn certificate_to_der(parsed: &ParsedPfx) -> Result<Vec<u8>, ConversionError> {
+        Self::cert_to_der(parsed.require_certificate()?)
+    }
+
+    /// Convert a single certificate to DER format
+    pub fn cert_to_der(cert: &X509) -> Result<Vec<u8>, ConversionError> {
+        Ok(cert.to_der()?)
+    }
+
+    /// Convert individual chain certificates to DER format
+    pub fn chain_certs_to_der(parsed: &ParsedPfx) -> Result<Vec<Vec<u8>>, ConversionError> {
+        parsed.chain.iter().map(Self::cert_to_der).collect()
+    }
+}
+
+/// Formatter for packing certificates and keys into a PFX/P12 file
+pub struct PfxFormatter;
+
+impl PfxFormatter {
+    /// Build a password-protected PFX (DER) from parsed key, certificate and chain
+    pub fn to_pfx(
+        parsed: &ParsedPfx,
+        friendly_name: Option<&str>,
+        password: &str,
+    ) -> Result<Vec<u8>, ConversionError> {
+        let mut builder = Pkcs12::builder();
+        if let Some(ref private_key) = parsed.private_key {
+            builder.pkey(private_key);
+        }
+        if let Some(ref certificate) = parsed.certificate {
+            builder.cert(certificate);
+        }
+
+        if let Some(name) = friendly_name {
+            builder.name(name);
+        }
+
+        if parsed.has_chain() {
+            let mut ca = Stack::new()?;
+            for cert in &parsed.chain {
+                ca.push(cert.clone())?;
+            }
+            builder.ca(ca);
+        }
+
+        let pkcs12 = builder.build2(password)?;
+        Ok(pkcs12.to_der()?)
+    }
+}
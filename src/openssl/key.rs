@@ -0,0 +1,151 @@
+use crate::error::ConversionError;
+use crate::openssl::pkcs8::{self, KeyEncryption};
+use openssl::pkey::{Id, PKeyRef, Private};
+use std::fmt;
+
+/// Encoding of a written private key
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum KeyFormat {
+    /// PKCS#8 (`BEGIN PRIVATE KEY`), works for every key type
+    #[default]
+    Pkcs8,
+    /// PKCS#1 (`BEGIN RSA PRIVATE KEY`), RSA keys only
+    Pkcs1,
+    /// SEC1 (`BEGIN EC PRIVATE KEY`), EC keys only
+    Sec1,
+    /// PKCS#1 for RSA keys and SEC1 for EC keys
+    Traditional,
+}
+
+impl fmt::Display for KeyFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyFormat::Pkcs8 => write!(f, "PKCS#8"),
+            KeyFormat::Pkcs1 => write!(f, "PKCS#1"),
+            KeyFormat::Sec1 => write!(f, "SEC1"),
+            KeyFormat::Traditional => write!(f, "traditional"),
+        }
+    }
+}
+
+impl KeyFormat {
+    /// Resolve the concrete format for a key, checking that the key type supports it
+    pub fn resolve(self, key: &PKeyRef<Private>) -> Result<KeyFormat, ConversionError> {
+        let id = key.id();
+        match self {
+            KeyFormat::Pkcs8 => Ok(KeyFormat::Pkcs8),
+            KeyFormat::Pkcs1 if id == Id::RSA => Ok(KeyFormat::Pkcs1),
+            KeyFormat::Sec1 if id == Id::EC => Ok(KeyFormat::Sec1),
+            KeyFormat::Traditional if id == Id::RSA => Ok(KeyFormat::Pkcs1),
+            KeyFormat::Traditional if id == Id::EC => Ok(KeyFormat::Sec1),
+            KeyFormat::Pkcs1 => Err(incompatible("PKCS#1", "an RSA", id)),
+            KeyFormat::Sec1 => Err(incompatible("SEC1", "an EC", id)),
+            KeyFormat::Traditional => Err(incompatible("A traditional", "an RSA or EC", id)),
+        }
+    }
+}
+
+/// Encoding of written keys and certificates
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputEncoding {
+    /// Base64 PEM text files
+    #[default]
+    Pem,
+    /// Binary DER files (`.der` keys, `.cer` certificates)
+    Der,
+    /// Both PEM and DER files
+    Both,
+}
+
+impl fmt::Display for OutputEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OutputEncoding::Pem => write!(f, "PEM"),
+            OutputEncoding::Der => write!(f, "DER"),
+            OutputEncoding::Both => write!(f, "PEM + DER"),
+        }
+    }
+}
+
+impl OutputEncoding {
+    /// Check whether PEM files are written
+    pub fn writes_pem(self) -> bool {
+        self != OutputEncoding::Der
+    }
+
+    /// Check whether DER files are written
+    pub fn writes_der(self) -> bool {
+        self != OutputEncoding::Pem
+    }
+}
+
+/// How private keys are written: their format and optional encryption
+#[derive(Debug, Clone, Default)]
+pub struct KeyOutput {
+    /// Requested key format
+    pub format: KeyFormat,
+    /// Encrypt the key with these settings; encrypted keys are always PKCS#8
+    pub encryption: Option<KeyEncryption>,
+}
+
+impl KeyOutput {
+    /// Encode a private key as PEM
+    pub fn to_pem(&self, key: &PKeyRef<Private>) -> Result<Vec<u8>, ConversionError> {
+        if let Some(ref encryption) = self.encryption {
+            return pkcs8::encrypt_to_pem(key, encryption);
+        }
+
+        let pem = match self.format.resolve(key)? {
+            KeyFormat::Pkcs1 => key.rsa()?.private_key_to_pem()?,
+            KeyFormat::Sec1 => key.ec_key()?.private_key_to_pem()?,
+            _ => key.private_key_to_pem_pkcs8()?,
+        };
+        Ok(pem)
+    }
+
+    /// Encode a private key as DER
+    pub fn to_der(&self, key: &PKeyRef<Private>) -> Result<Vec<u8>, ConversionError> {
+        if let Some(ref encryption) = self.encryption {
+            return pkcs8::encrypt_to_der(key, encryption);
+        }
+
+        let der = match self.format.resolve(key)? {
+            KeyFormat::Pkcs1 => key.rsa()?.private_key_to_der()?,
+            KeyFormat::Sec1 => key.ec_key()?.private_key_to_der()?,
+            _ => key.private_key_to_pkcs8()?,
+        };
+        Ok(der)
+    }
+
+    /// Describe the format a key is written in, e.g. "PKCS#1 PEM"
+    pub fn description(&self, key: &PKeyRef<Private>, encoding: OutputEncoding) -> String {
+        match (&self.encryption, self.format.resolve(key)) {
+            (Some(_), _) => format!("Encrypted PKCS#8 {encoding}"),
+            (None, Ok(format)) => format!("{format} {encoding}"),
+            (None, Err(_)) => format!("{} {encoding}", self.format),
+        }
+    }
+}
+
+/// Human readable name of a key type
+pub fn key_type_name(id: Id) -> &'static str {
+    match id {
+        Id::RSA => "RSA",
+        Id::RSA_PSS => "RSA-PSS",
+        Id::DSA => "DSA",
+        Id::DH => "DH",
+        Id::EC => "EC",
+        Id::ED25519 => "Ed25519",
+        Id::ED448 => "Ed448",
+        Id::X25519 => "X25519",
+        Id::X448 => "X448",
+        _ => "unknown",
+    }
+}
+
+fn incompatible(format: &str, required: &str, id: Id) -> ConversionError {
+    ConversionError::InvalidOption(format!(
+        "{format} key format requires {required} key, but the private key is {}",
+        key_type_name(id)
+    ))
+}
@@ -0,0 +1,110 @@
+use crate::error::ConversionError;
+use crate::openssl::{ParsedPfx, key_type_name};
+use openssl::error::ErrorStack;
+use openssl::hash::MessageDigest;
+use openssl::pkey::{HasPrivate, HasPublic, Id, PKeyRef};
+use openssl::rand::rand_bytes;
+use openssl::sign::{Signer, Verifier};
+use openssl::x509::X509Ref;
+
+impl ParsedPfx {
+    /// Check whether the private key belongs to the main certificate
+    ///
+    /// Returns `None` when the key or the certificate is missing.
+    pub fn key_matches_certificate(&self) -> Option<bool> {
+        let key = self.private_key.as_ref()?;
+        let certificate = self.certificate.as_ref()?;
+        Some(certificate_matches(certificate, key))
+    }
+
+    /// Make the certificate that belongs to the private key the main certificate
+    ///
+    /// When the main certificate does not match the key but a chain certificate
+    /// does, they trade places. Returns whether the main certificate changed.
+    pub fn select_leaf(&mut self) -> bool {
+        let Some(ref key) = self.private_key else {
+            return false;
+        };
+        if self
+            .certificate
+            .as_ref()
+            .is_some_and(|cert| certificate_matches(cert, key))
+        {
+            return false;
+        }
+
+        let Some(index) = self
+            .chain
+            .iter()
+            .position(|cert| certificate_matches(cert, key))
+        else {
+            return false;
+        };
+        let leaf = self.chain.remove(index);
+        if let Some(previous) = self.certificate.replace(leaf) {
+            self.chain.insert(0, previous);
+        }
+        true
+    }
+
+    /// Fail unless the private key belongs to the main certificate
+    ///
+    /// Public keys are compared; with `round_trip`, test data is also signed
+    /// with the private key and verified with the certificate. Files missing
+    /// the key or the certificate pass, as there is nothing to compare.
+    pub fn check_key_match(&self, round_trip: bool) -> Result<(), ConversionError> {
+        let (Some(key), Some(certificate)) = (&self.private_key, &self.certificate) else {
+            return Ok(());
+        };
+
+        if !certificate_matches(certificate, key) {
+            return Err(ConversionError::KeyMismatch(
+                "the certificate's public key belongs to a different private key".to_string(),
+            ));
+        }
+
+        let public = certificate.public_key()?;
+        if round_trip && !sign_round_trip(key, &public)? {
+            return Err(ConversionError::KeyMismatch(format!(
+                "a signature made with the {} private key does not verify with the certificate",
+                key_type_name(key.id())
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+/// Check whether a certificate holds the public half of a private key
+fn certificate_matches<T: HasPublic>(certificate: &X509Ref, key: &PKeyRef<T>) -> bool {
+    certificate
+        .public_key()
+        .map(|public| public.public_eq(key))
+        .unwrap_or(false)
+}
+
+/// Sign random data with the private key and verify it with the public key
+///
+/// Key types that cannot sign (e.g. X25519) pass on the public key comparison alone.
+fn sign_round_trip<K: HasPrivate, P: HasPublic>(
+    key: &PKeyRef<K>,
+    public: &PKeyRef<P>,
+) -> Result<bool, ErrorStack> {
+    let mut data = [0u8; 32];
+    rand_bytes(&mut data)?;
+
+    let signature = match key.id() {
+        Id::RSA | Id::RSA_PSS | Id::DSA | Id::EC => {
+            Signer::new(MessageDigest::sha256(), key)?.sign_oneshot_to_vec(&data)?
+        }
+        Id::ED25519 | Id::ED448 => Signer::new_without_digest(key)?.sign_oneshot_to_vec(&data)?,
+        _ => return Ok(true),
+    };
+
+    match key.id() {
+        Id::ED25519 | Id::ED448 => {
+            Verifier::new_without_digest(public)?.verify_oneshot(&signature, &data)
+        }
+        _ => Verifier::new(MessageDigest::sha256(), public)?.verify_oneshot(&signature, &data),
+    }
+}
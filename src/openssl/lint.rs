@@ -0,0 +1,171 @@
+use crate::openssl::{CertificateDetails, ParsedPfx, TimeZone, format_date, is_self_signed};
+use openssl::asn1::Asn1Time;
+use openssl::x509::X509;
+use std::fmt;
+
+/// Smallest RSA key size that is not considered weak
+pub const MIN_RSA_BITS: u32 = 2048;
+
+/// Longest validity of a TLS server certificate accepted by browsers (CA/Browser Forum)
+pub const MAX_VALIDITY_DAYS: i64 = 398;
+
+/// How serious a finding is; `--strict` fails the conversion on errors
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// A rule violated by the key or a certificate
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintFinding {
+    /// Name of the violated rule, e.g. `weak-rsa-key`
+    pub rule: String,
+    pub severity: Severity,
+    /// Common name (or subject) of the certificate the finding is about
+    pub certificate: String,
+    /// What is wrong, e.g. "has a 1024-bit RSA key"
+    pub message: String,
+}
+
+impl LintFinding {
+    /// Create a finding about the certificate described by `details`
+    pub(crate) fn new(
+        rule: &str,
+        severity: Severity,
+        details: &CertificateDetails,
+        message: String,
+    ) -> Self {
+        Self {
+            rule: rule.to_string(),
+            severity,
+            certificate: details
+                .info
+                .common_name
+                .clone()
+                .unwrap_or_else(|| details.info.subject.clone()),
+            message,
+        }
+    }
+}
+
+impl ParsedPfx {
+    /// Check the certificates against the built-in security rules
+    ///
+    /// Weak keys and signatures and certificates outside their validity period
+    /// are errors. A main certificate without subject alternative names, with
+    /// `CA:TRUE` or valid for more than 398 days draws a warning. Self-signed
+    /// roots are exempt from the signature rule, as their signature is never
+    /// checked.
+    pub fn lint(&self) -> Vec<LintFinding> {
+        let mut findings = Vec::new();
+        for cert in &self.chain {
+            lint_certificate(cert, &mut findings);
+        }
+
+        let Some(ref certificate) = self.certificate else {
+            return findings;
+        };
+        let mut leaf_findings = Vec::new();
+        let details = lint_certificate(certificate, &mut leaf_findings);
+
+        if details.subject_alt_names.is_empty() {
+            leaf_findings.push(LintFinding::new(
+                "missing-san",
+                Severity::Warning,
+                &details,
+                "has no subject alternative names; TLS clients ignore the common name".to_string(),
+            ));
+        }
+        if details.basic_constraints.is_some_and(|bc| bc.ca) {
+            leaf_findings.push(LintFinding::new(
+                "ca-leaf",
+                Severity::Warning,
+                &details,
+                "is marked CA:TRUE but is used as the main certificate".to_string(),
+            ));
+        }
+        let validity = certificate
+            .not_before()
+            .diff(certificate.not_after())
+            .map(|diff| i64::from(diff.days) + i64::from(diff.secs > 0))
+            .unwrap_or_default();
+        if validity > MAX_VALIDITY_DAYS {
+            leaf_findings.push(LintFinding::new(
+                "long-validity",
+                Severity::Warning,
+                &details,
+                format!(
+                    "is valid for {validity} days; browsers reject more than {MAX_VALIDITY_DAYS}"
+                ),
+            ));
+        }
+
+        // Findings about the main certificate come first
+        leaf_findings.extend(findings);
+        leaf_findings
+    }
+}
+
+/// Apply the rules shared by every certificate, returning its details
+fn lint_certificate(cert: &X509, findings: &mut Vec<LintFinding>) -> CertificateDetails {
+    let details = CertificateDetails::from_x509(cert);
+
+    if details.public_key_algorithm == "RSA" && details.public_key_bits < MIN_RSA_BITS {
+        findings.push(LintFinding::new(
+            "weak-rsa-key",
+            Severity::Error,
+            &details,
+            format!(
+                "has a {}-bit RSA key; at least {MIN_RSA_BITS} bits are required",
+                details.public_key_bits
+            ),
+        ));
+    }
+
+    let algorithm = &details.info.signature_algorithm;
+    let lower = algorithm.to_lowercase();
+    if (lower.contains("sha1") || lower.contains("md5")) && !is_self_signed(cert) {
+        findings.push(LintFinding::new(
+            "weak-signature",
+            Severity::Error,
+            &details,
+            format!("is signed with {algorithm}; SHA-1 and MD5 signatures can be forged"),
+        ));
+    }
+
+    if let Ok(now) = Asn1Time::days_from_now(0) {
+        if cert.not_after() < now {
+            findings.push(LintFinding::new(
+                "expired",
+                Severity::Error,
+                &details,
+                format!(
+                    "expired on {}",
+                    format_date(details.info.not_after, TimeZone::Utc)
+                ),
+            ));
+        } else if cert.not_before() > now {
+            findings.push(LintFinding::new(
+                "not-yet-valid",
+                Severity::Error,
+                &details,
+                format!(
+                    "is not valid before {}",
+                    format_date(details.info.not_before, TimeZone::Utc)
+                ),
+            ));
+        }
+    }
+
+    details
+}
@@ -1,21 +1,47 @@
+mod chain;
+mod der;
+mod details;
+mod detect;
+mod expiry;
 mod formatter;
+mod key;
+mod key_match;
+mod lint;
+mod name;
 pub mod parser;
+mod pkcs8;
+mod safebag;
+mod time;
+mod verify;
 
-pub use formatter::PemFormatter;
-pub use parser::PfxParser;
+pub use chain::{ChainReport, is_issuer, is_self_signed};
+pub use der::oid_name;
+pub use details::{BasicConstraints, CertificateDetails};
+pub use detect::InputFormat;
+pub use expiry::{CertificateExpiry, ExpiryStatus, ExpiryThresholds};
+pub use formatter::{DerFormatter, PemFormatter, PfxFormatter};
+pub use key::{KeyFormat, KeyOutput, OutputEncoding, key_type_name};
+pub use lint::{LintFinding, MAX_VALIDITY_DAYS, MIN_RSA_BITS, Severity};
+pub use name::{DistinguishedName, NameComponent, NameFormat};
+pub use parser::{PemParser, PfxParser};
+pub use pkcs8::{DEFAULT_PBKDF2_ITERATIONS, DEFAULT_SCRYPT_COST, KeyDerivation, KeyEncryption};
+pub use safebag::PfxEncryption;
+pub use time::{SECONDS_PER_DAY, TimeZone, Timestamp, format_date};
+pub use verify::{ChainStep, TrustStore, Verification};
 
+use crate::error::ConversionError;
 use openssl::pkcs12::ParsedPkcs12_2 as ParsedPkcs12;
 use openssl::pkey::PKey;
 use openssl::pkey::Private;
 use openssl::x509::X509;
 
 /// Represents the contents of a parsed PFX file
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ParsedPfx {
-    /// The private key
-    pub private_key: PKey<Private>,
-    /// The main certificate
-    pub certificate: X509,
+    /// The private key (absent for certificate-only files such as trust stores)
+    pub private_key: Option<PKey<Private>>,
+    /// The main certificate (absent when no certificate belongs to a private key)
+    pub certificate: Option<X509>,
     /// Additional certificates in the chain (if any)
     pub chain: Vec<X509>,
 }
@@ -23,8 +49,8 @@ pub struct ParsedPfx {
 impl From<ParsedPkcs12> for ParsedPfx {
     fn from(parsed: ParsedPkcs12) -> Self {
         Self {
-            private_key: parsed.pkey.expect("Private key is required"),
-            certificate: parsed.cert.expect("Certificate is required"),
+            private_key: parsed.pkey,
+            certificate: parsed.cert,
             chain: parsed
                 .ca
                 .map(|stack| stack.into_iter().collect())
@@ -33,30 +59,46 @@ impl From<ParsedPkcs12> for ParsedPfx {
     }
 }
 
+/// A private key and its certificate stored in a PFX file under one alias
+#[derive(Debug, Clone)]
+pub struct PfxIdentity {
+    /// The friendlyName attribute of the key or certificate, if any
+    pub friendly_name: Option<String>,
+    /// The key, its certificate and the chain certificates shared by all identities
+    pub contents: ParsedPfx,
+}
+
 /// Certificate information structure
 #[derive(Debug, Clone)]
 pub struct CertificateInfo {
+    /// Subject as an RFC 4514 string
     pub subject: String,
+    /// The individual attributes of the subject
+    pub subject_name: DistinguishedName,
+    pub common_name: Option<String>,
+    /// Issuer as an RFC 4514 string
     pub issuer: String,
+    /// The individual attributes of the issuer
+    pub issuer_name: DistinguishedName,
     pub serial_number: String,
-    pub not_before: String,
-    pub not_after: String,
+    /// Start of the validity period, `None` if OpenSSL cannot read it
+    pub not_before: Option<Timestamp>,
+    /// End of the validity period, `None` if OpenSSL cannot read it
+    pub not_after: Option<Timestamp>,
     pub signature_algorithm: String,
 }
 
-impl ParsedPfx {
-    /// Get certificate information as a formatted string (legacy method)
-    pub fn cert_info(&self) -> String {
-        let info = self.certificate_info();
-        format!("Subject: {}\nIssuer: {}", info.subject, info.issuer)
-    }
-
-    /// Get detailed certificate information
-    pub fn certificate_info(&self) -> CertificateInfo {
-        let cert = &self.certificate;
-        CertificateInfo {
-            subject: format!("{:?}", cert.subject_name()),
-            issuer: format!("{:?}", cert.issuer_name()),
+impl CertificateInfo {
+    /// Collect information about a single certificate
+    pub fn from_x509(cert: &X509) -> Self {
+        let subject_name = DistinguishedName::from_x509_name(cert.subject_name());
+        let issuer_name = DistinguishedName::from_x509_name(cert.issuer_name());
+        Self {
+            subject: subject_name.to_string(),
+            common_name: subject_name.common_name().map(str::to_string),
+            subject_name,
+            issuer: issuer_name.to_string(),
+            issuer_name,
             serial_number: cert
                 .serial_number()
                 .to_bn()
@@ -64,11 +106,55 @@ impl ParsedPfx {
                 .to_hex_str()
                 .unwrap()
                 .to_string(),
-            not_before: cert.not_before().to_string(),
-            not_after: cert.not_after().to_string(),
+            not_before: Timestamp::from_asn1(cert.not_before()),
+            not_after: Timestamp::from_asn1(cert.not_after()),
             signature_algorithm: cert.signature_algorithm().object().to_string(),
         }
     }
+}
+
+impl ParsedPfx {
+    /// Get certificate information as a formatted string (legacy method)
+    pub fn cert_info(&self) -> String {
+        match self.certificate_info() {
+            Some(info) => format!("Subject: {}\nIssuer: {}", info.subject, info.issuer),
+            None => "No certificate".to_string(),
+        }
+    }
+
+    /// Get detailed information about the main certificate, if present
+    pub fn certificate_info(&self) -> Option<CertificateInfo> {
+        self.certificate.as_ref().map(CertificateInfo::from_x509)
+    }
+
+    /// Get the private key, or an error if the PFX does not contain one
+    pub fn require_private_key(&self) -> Result<&PKey<Private>, ConversionError> {
+        self.private_key
+            .as_ref()
+            .ok_or_else(|| ConversionError::MissingComponent("private key".to_string()))
+    }
+
+    /// Get the main certificate, or an error if the PFX does not contain one
+    pub fn require_certificate(&self) -> Result<&X509, ConversionError> {
+        self.certificate
+            .as_ref()
+            .ok_or_else(|| ConversionError::MissingComponent("certificate".to_string()))
+    }
+
+    /// Check if this PFX contains a private key
+    pub fn has_private_key(&self) -> bool {
+        self.private_key.is_some()
+    }
+
+    /// Check if this PFX contains a main certificate
+    pub fn has_certificate(&self) -> bool {
+        self.certificate.is_some()
+    }
+
+    /// Check if this PFX contains nothing that can be extracted
+    pub fn is_empty(&self) -> bool {
+        self.private_key.is_none() && self.certificate.is_none() && self.chain.is_empty()
+    }
 
     /// Check if this PFX contains a certificate chain
     pub fn has_chain(&self) -> bool {
@@ -0,0 +1,241 @@
+use crate::openssl::der::{self, DerReader};
+use foreign_types::ForeignTypeRef;
+use openssl::asn1::{Asn1ObjectRef, Asn1StringRef};
+use openssl::error::ErrorStack;
+use openssl::nid::Nid;
+use openssl::x509::{X509NameEntryRef, X509NameRef};
+use std::ffi::c_int;
+use std::{fmt, ptr, slice};
+
+/// How distinguished names are written
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NameFormat {
+    /// RFC 4514, most specific attribute first: `CN=example.com,O=Example,C=US`
+    #[default]
+    Rfc4514,
+    /// OpenSSL's one-line style, in stored order: `C = US, O = Example, CN = example.com`
+    Oneline,
+}
+
+/// One attribute of a distinguished name, e.g. `CN=example.com`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NameComponent {
+    /// Short attribute type (`CN`, `O`, `OU`, `C`...), or the dotted OID if unknown
+    pub key: String,
+    /// The attribute type in dotted notation
+    pub oid: String,
+    /// The decoded attribute value
+    pub value: String,
+    /// Position of the RDN holding the attribute; attributes sharing one form a
+    /// multi-valued RDN such as `CN=a+OU=b`
+    pub rdn: usize,
+}
+
+/// A distinguished name, with its attributes in stored order (most general first)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DistinguishedName {
+    pub components: Vec<NameComponent>,
+}
+
+impl DistinguishedName {
+    /// Decode the attributes of an X.509 name
+    pub fn from_x509_name(name: &X509NameRef) -> Self {
+        let mut rdns = rdn_indices(name).unwrap_or_default().into_iter();
+        Self {
+            components: name
+                .entries()
+                .enumerate()
+                .map(|(index, entry)| component(entry, rdns.next().unwrap_or(index)))
+                .collect(),
+        }
+    }
+
+    /// Get every value of an attribute type, e.g. all `OU`s, in stored order
+    pub fn get(&self, key: &str) -> Vec<&str> {
+        self.components
+            .iter()
+            .filter(|c| c.key.eq_ignore_ascii_case(key) || c.oid == key)
+            .map(|c| c.value.as_str())
+            .collect()
+    }
+
+    /// The first common name (`CN`), if any
+    pub fn common_name(&self) -> Option<&str> {
+        self.get("CN").first().copied()
+    }
+
+    /// The first organization (`O`), if any
+    pub fn organization(&self) -> Option<&str> {
+        self.get("O").first().copied()
+    }
+
+    /// Every organizational unit (`OU`)
+    pub fn organizational_units(&self) -> Vec<&str> {
+        self.get("OU")
+    }
+
+    /// The country (`C`), if any
+    pub fn country(&self) -> Option<&str> {
+        self.get("C").first().copied()
+    }
+
+    /// The attributes grouped by RDN, in stored order
+    pub fn rdns(&self) -> Vec<&[NameComponent]> {
+        self.components.chunk_by(|a, b| a.rdn == b.rdn).collect()
+    }
+
+    /// Write the name in the given style
+    ///
+    /// The attributes of a multi-valued RDN are joined with `+`.
+    pub fn format(&self, format: NameFormat) -> String {
+        match format {
+            NameFormat::Rfc4514 => self
+                .rdns()
+                .iter()
+                .rev()
+                .map(|rdn| {
+                    rdn.iter()
+                        .map(|c| format!("{}={}", c.key, escape_rfc4514(&c.value)))
+                        .collect::<Vec<_>>()
+                        .join("+")
+                })
+                .collect::<Vec<_>>()
+                .join(","),
+            NameFormat::Oneline => self
+                .rdns()
+                .iter()
+                .map(|rdn| {
+                    rdn.iter()
+                        .map(|c| format!("{} = {}", c.key, quote_oneline(&c.value)))
+                        .collect::<Vec<_>>()
+                        .join(" + ")
+                })
+                .collect::<Vec<_>>()
+                .join(", "),
+        }
+    }
+}
+
+impl fmt::Display for DistinguishedName {
+    /// Write the name as an RFC 4514 string
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.format(NameFormat::Rfc4514))
+    }
+}
+
+/// The RDN index of every attribute, in stored order, read from the DER encoding
+///
+/// A name is a SEQUENCE of RDNs, each a SET of one or more attributes.
+fn rdn_indices(name: &X509NameRef) -> Option<Vec<usize>> {
+    let der = name.to_der().ok()?;
+    let mut rdns = DerReader::new(&der)
+        .expect(der::TAG_SEQUENCE)
+        .ok()?
+        .reader();
+    let mut indices = Vec::new();
+    let mut rdn = 0;
+    while !rdns.is_empty() {
+        let mut attributes = rdns.expect(der::TAG_SET).ok()?.reader();
+        while !attributes.is_empty() {
+            attributes.expect(der::TAG_SEQUENCE).ok()?;
+            indices.push(rdn);
+        }
+        rdn += 1;
+    }
+    Some(indices)
+}
+
+fn component(entry: &X509NameEntryRef, rdn: usize) -> NameComponent {
+    let object = entry.object();
+    let oid = dotted_oid(object);
+    let key = match object.nid() {
+        Nid::UNDEF => oid.clone(),
+        // RFC 4514 spells the street attribute in capitals
+        Nid::STREETADDRESS => "STREET".to_string(),
+        nid => nid
+            .short_name()
+            .map_or_else(|_| oid.clone(), str::to_string),
+    };
+    let value = utf8_value(entry.data())
+        .unwrap_or_else(|| String::from_utf8_lossy(entry.data().as_slice()).into_owned());
+
+    NameComponent {
+        key,
+        oid,
+        value,
+        rdn,
+    }
+}
+
+/// The numeric form of an attribute type, e.g. `2.5.4.3`
+fn dotted_oid(object: &Asn1ObjectRef) -> String {
+    // SAFETY: a null buffer of length 0 only asks for the length of the text
+    let len = unsafe { openssl_sys::OBJ_obj2txt(ptr::null_mut(), 0, object.as_ptr(), 1) };
+    let Ok(len) = usize::try_from(len) else {
+        return String::new();
+    };
+    let mut buf = vec![0u8; len + 1];
+    // SAFETY: the buffer has room for the text and its NUL terminator
+    unsafe {
+        openssl_sys::OBJ_obj2txt(
+            buf.as_mut_ptr().cast(),
+            buf.len() as c_int,
+            object.as_ptr(),
+            1,
+        )
+    };
+    buf.truncate(len);
+    String::from_utf8_lossy(&buf).into_owned()
+}
+
+/// An attribute value converted to UTF-8, keeping interior NUL bytes
+fn utf8_value(data: &Asn1StringRef) -> Option<String> {
+    let mut out = ptr::null_mut();
+    // SAFETY: on success `out` holds `len` bytes allocated by OpenSSL
+    let len = unsafe { openssl_sys::ASN1_STRING_to_UTF8(&mut out, data.as_ptr()) };
+    let Ok(len) = usize::try_from(len) else {
+        // Clear the error queue for the next OpenSSL call
+        ErrorStack::get();
+        return None;
+    };
+    let value = if out.is_null() {
+        String::new()
+    } else {
+        // SAFETY: `out` points at `len` bytes and is freed once they are copied
+        let value =
+            String::from_utf8_lossy(unsafe { slice::from_raw_parts(out, len) }).into_owned();
+        unsafe { openssl_sys::OPENSSL_free(out.cast()) };
+        value
+    };
+    Some(value)
+}
+
+/// Escape an attribute value as described in RFC 4514, section 2.4
+fn escape_rfc4514(value: &str) -> String {
+    let last = value.chars().count().saturating_sub(1);
+    let mut escaped = String::with_capacity(value.len());
+    for (i, ch) in value.chars().enumerate() {
+        match ch {
+            '"' | '+' | ',' | ';' | '<' | '>' | '\\' => {
+                escaped.push('\\');
+                escaped.push(ch);
+            }
+            '#' if i == 0 => escaped.push_str("\\#"),
+            ' ' if i == 0 || i == last => escaped.push_str("\\ "),
+            '\0' => escaped.push_str("\\00"),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+/// Quote an attribute value containing separators, as OpenSSL's one-line style does
+fn quote_oneline(value: &str) -> String {
+    let needs_quotes = value.starts_with([' ', '#'])
+        || value.ends_with(' ')
+        || value.contains([',', '+', ';', '<', '>', '"', '\\']);
+    if !needs_quotes {
+        return value.to_string();
+    }
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
@@ -1,9 +1,16 @@
 use crate::error::ConversionError;
-use crate::openssl::ParsedPfx;
+use crate::openssl::detect;
+use crate::openssl::safebag;
+use crate::openssl::{ParsedPfx, PfxEncryption, PfxIdentity};
+use openssl::error::ErrorStack;
 use openssl::pkcs12::Pkcs12;
-use std::ffi::OsStr;
+use openssl::pkey::PKey;
+use openssl::provider::Provider;
+use openssl::x509::X509;
 use std::fs;
+use std::io::Read;
 use std::path::Path;
+use std::sync::OnceLock;
 
 /// Parser for PFX/P12 files
 pub struct PfxParser;
@@ -14,8 +21,181 @@ impl PfxParser {
         path: P,
         password: &str,
     ) -> Result<ParsedPfx, ConversionError> {
-        let path = path.as_ref();
+        let pfx_data = Self::read_file(path.as_ref())?;
+        Self::parse_bytes(&pfx_data, password)
+    }
+
+    /// Parse every identity (key/certificate pair) from a PFX file path
+    pub fn parse_file_identities<P: AsRef<Path>>(
+        path: P,
+        password: &str,
+    ) -> Result<Vec<PfxIdentity>, ConversionError> {
+        let pfx_data = Self::read_file(path.as_ref())?;
+        Self::parse_identities(&pfx_data, password)
+    }
+
+    /// Parse PFX (DER) data from a reader, such as stdin
+    pub fn parse_reader<R: Read>(reader: R, password: &str) -> Result<ParsedPfx, ConversionError> {
+        let pfx_data = Self::read_reader(reader)?;
+        Self::parse_bytes(&pfx_data, password)
+    }
+
+    /// Parse PFX data from bytes
+    pub fn parse_bytes(data: &[u8], password: &str) -> Result<ParsedPfx, ConversionError> {
+        // Validate input data
+        if data.is_empty() {
+            return Err(ConversionError::InvalidFormat(
+                "Empty PFX data provided".to_string(),
+            ));
+        }
+
+        // Accept DER or base64 PKCS#12, rejecting anything else by what it is
+        let data = detect::pkcs12_der(data)?;
+
+        // Parse the PKCS12 structure
+        let pkcs12 = Pkcs12::from_der(&data).map_err(|e| {
+            ConversionError::InvalidFormat(format!("Failed to parse PFX structure: {e}"))
+        })?;
+
+        // Extract the contents with the provided password
+        let parsed = pkcs12.parse2(password).map_err(|e| {
+            if is_unsupported_algorithm(&e) {
+                let algorithms = safebag::read_encryption(&data)
+                    .map(|encryption| encryption.legacy_algorithms().join(", "))
+                    .unwrap_or_default();
+                let algorithms = if algorithms.is_empty() {
+                    "a legacy algorithm".to_string()
+                } else {
+                    algorithms
+                };
+                ConversionError::UnsupportedAlgorithm(format!(
+                    "PFX file is encrypted with {algorithms}, which OpenSSL 3 only supports \
+                     through its legacy provider. Re-run with --legacy to enable it ({e})"
+                ))
+            } else if password.is_empty() {
+                ConversionError::Authentication(format!(
+                    "Failed to parse PFX file: {e}. This file may require a password. \
+                     Use --password-env, --password-file, --password-fd or --password."
+                ))
+            } else {
+                ConversionError::Authentication(format!(
+                    "Failed to parse PFX file with provided password: {e}"
+                ))
+            }
+        })?;
+
+        let mut parsed = ParsedPfx::from(parsed);
+        parsed.select_leaf();
+        if parsed.is_empty() {
+            return Err(ConversionError::InvalidFormat(
+                "PFX file contains no certificates or private keys".to_string(),
+            ));
+        }
+
+        Ok(parsed)
+    }
+
+    /// Read the encryption and MAC algorithms of PFX data without the password
+    pub fn encryption_info(data: &[u8]) -> Result<PfxEncryption, ConversionError> {
+        let data = detect::pkcs12_der(data)?;
+        safebag::read_encryption(&data)
+    }
+
+    /// Load OpenSSL's legacy provider so RC2/RC4/DES encrypted PFX files can be read
+    ///
+    /// The provider stays loaded for the rest of the process.
+    pub fn enable_legacy_provider() -> Result<(), ConversionError> {
+        static PROVIDERS: OnceLock<(Provider, Provider)> = OnceLock::new();
+
+        if PROVIDERS.get().is_some() {
+            return Ok(());
+        }
+
+        let load = |name: &str| {
+            Provider::try_load(None, name, true).map_err(|e| {
+                ConversionError::UnsupportedAlgorithm(format!(
+                    "Failed to load OpenSSL {name} provider: {e}"
+                ))
+            })
+        };
+        let providers = (load("default")?, load("legacy")?);
+        let _ = PROVIDERS.set(providers);
+        Ok(())
+    }
+
+    /// Parse every identity (key/certificate pair) from PFX data
+    ///
+    /// Every private key SafeBag is enumerated and paired with the certificate
+    /// holding its public key. Certificates that do not belong to any key form
+    /// the chain shared by every identity. Files with at most one key yield a
+    /// single identity.
+    ///
+    /// Structures the bag walker cannot read (e.g. BER with indefinite lengths)
+    /// fail with `IncompleteIdentities` rather than silently dropping identities;
+    /// `parse_bytes` still extracts the primary identity of such files.
+    pub fn parse_identities(
+        data: &[u8],
+        password: &str,
+    ) -> Result<Vec<PfxIdentity>, ConversionError> {
+        let data = detect::pkcs12_der(data)?;
+
+        // OpenSSL verifies the MAC and password, reports legacy algorithms and
+        // decrypts the certificates and the first key; the bag walker only
+        // decrypts the keys that follow.
+        let parsed = Self::parse_bytes(&data, password)?;
+
+        let key_bags = safebag::read_key_bags(&data, password)
+            .map_err(|e| ConversionError::IncompleteIdentities(e.to_string()))?;
+
+        if key_bags.len() <= 1 {
+            let friendly_name = key_bags
+                .into_iter()
+                .find_map(|bag| bag.friendly_name)
+                .or_else(|| parsed.certificate.as_ref().and_then(certificate_alias));
+            return Ok(vec![PfxIdentity {
+                friendly_name,
+                contents: parsed,
+            }]);
+        }
 
+        let mut certs: Vec<X509> = parsed.certificate.into_iter().chain(parsed.chain).collect();
+        let mut primary_key = parsed.private_key;
+
+        // Pair each key with its certificate
+        let mut paired = Vec::new();
+        for bag in key_bags {
+            let Some(key) = bag.key.or_else(|| primary_key.take()) else {
+                continue;
+            };
+            let certificate = certs
+                .iter()
+                .position(|cert| {
+                    cert.public_key()
+                        .map(|public| public.public_eq(&key))
+                        .unwrap_or(false)
+                })
+                .map(|index| certs.remove(index));
+            let friendly_name = bag
+                .friendly_name
+                .or_else(|| certificate.as_ref().and_then(certificate_alias));
+            paired.push((key, certificate, friendly_name));
+        }
+
+        Ok(paired
+            .into_iter()
+            .map(|(key, certificate, friendly_name)| PfxIdentity {
+                friendly_name,
+                contents: ParsedPfx {
+                    private_key: Some(key),
+                    certificate,
+                    chain: certs.clone(),
+                },
+            })
+            .collect())
+    }
+
+    /// Read PFX data from a file path with proper error handling
+    pub fn read_file(path: &Path) -> Result<Vec<u8>, ConversionError> {
         // Check if file exists
         if !path.exists() {
             return Err(ConversionError::FileNotFound(path.display().to_string()));
@@ -29,14 +209,6 @@ impl PfxParser {
             ));
         }
 
-        // Validate file extension (if it has one)
-        if let Some(ext) = path.extension().and_then(OsStr::to_str) {
-            let ext = ext.to_lowercase();
-            if ext != "pfx" && ext != "p12" {
-                return Err(ConversionError::InvalidFileExtension(ext));
-            }
-        }
-
         // Read the file
         let pfx_data =
             fs::read(path).map_err(|e| ConversionError::FileRead(path.display().to_string(), e))?;
@@ -46,34 +218,103 @@ impl PfxParser {
             return Err(ConversionError::InvalidFormat("File is empty".to_string()));
         }
 
-        Self::parse_bytes(&pfx_data, password)
+        Ok(pfx_data)
     }
 
-    /// Parse PFX data from bytes
-    pub fn parse_bytes(data: &[u8], password: &str) -> Result<ParsedPfx, ConversionError> {
-        // Validate input data
-        if data.is_empty() {
-            return Err(ConversionError::InvalidFormat(
-                "Empty PFX data provided".to_string(),
-            ));
+    /// Read all PFX data from a reader with proper error handling
+    pub fn read_reader<R: Read>(mut reader: R) -> Result<Vec<u8>, ConversionError> {
+        let mut pfx_data = Vec::new();
+        reader
+            .read_to_end(&mut pfx_data)
+            .map_err(|e| ConversionError::FileRead("<stdin>".to_string(), e))?;
+
+        if pfx_data.is_empty() {
+            return Err(ConversionError::InvalidFormat("Input is empty".to_string()));
         }
 
-        // Parse the PKCS12 structure
-        let pkcs12 = Pkcs12::from_der(data).map_err(|e| {
-            ConversionError::InvalidFormat(format!("Failed to parse PFX structure: {e}"))
+        Ok(pfx_data)
+    }
+}
+
+/// Check whether an OpenSSL error comes from a missing (e.g. legacy) algorithm
+fn is_unsupported_algorithm(err: &ErrorStack) -> bool {
+    err.errors()
+        .iter()
+        .any(|e| e.reason() == Some("unsupported"))
+}
+
+/// The friendlyName OpenSSL attached to a certificate read from a PFX
+fn certificate_alias(cert: &X509) -> Option<String> {
+    cert.alias()
+        .map(|alias| String::from_utf8_lossy(alias).into_owned())
+}
+
+/// Parser for PEM encoded keys, certificates and chains
+pub struct PemParser;
+
+impl PemParser {
+    /// Parse a private key, certificate and optional chain from PEM files
+    pub fn parse_files<P: AsRef<Path>>(
+        key_path: P,
+        cert_path: P,
+        chain_path: Option<P>,
+    ) -> Result<ParsedPfx, ConversionError> {
+        let key_pem = Self::read_file(key_path.as_ref())?;
+        let cert_pem = Self::read_file(cert_path.as_ref())?;
+        let chain_pem = chain_path
+            .map(|path| Self::read_file(path.as_ref()))
+            .transpose()?;
+
+        Self::parse_bytes(&key_pem, &cert_pem, chain_pem.as_deref())
+    }
+
+    /// Parse a private key, certificate and optional chain from PEM data
+    ///
+    /// If the certificate data contains more than one certificate, the first one
+    /// is used as the main certificate and the rest are treated as chain certificates,
+    /// unless another certificate (including the chain) matches the private key.
+    pub fn parse_bytes(
+        key_pem: &[u8],
+        cert_pem: &[u8],
+        chain_pem: Option<&[u8]>,
+    ) -> Result<ParsedPfx, ConversionError> {
+        let private_key = PKey::private_key_from_pem(key_pem).map_err(|e| {
+            ConversionError::InvalidPem(format!("Failed to parse private key: {e}"))
         })?;
 
-        // Extract the contents with the provided password
-        let parsed = pkcs12.parse2(password).map_err(|e| {
-            if password.is_empty() {
-                ConversionError::Authentication(format!(
-                    "Failed to parse PFX file: {e}. This file may require a password. Use --password option."))
-            } else {
-                ConversionError::Authentication(format!(
-                    "Failed to parse PFX file with provided password: {e}"))
-            }
+        let mut certs = X509::stack_from_pem(cert_pem).map_err(|e| {
+            ConversionError::InvalidPem(format!("Failed to parse certificate: {e}"))
         })?;
+        if certs.is_empty() {
+            return Err(ConversionError::InvalidPem(
+                "No certificate found in certificate file".to_string(),
+            ));
+        }
+        let certificate = certs.remove(0);
+
+        let mut chain = certs;
+        if let Some(chain_pem) = chain_pem {
+            let chain_certs = X509::stack_from_pem(chain_pem).map_err(|e| {
+                ConversionError::InvalidPem(format!("Failed to parse certificate chain: {e}"))
+            })?;
+            chain.extend(chain_certs);
+        }
+
+        let mut parsed = ParsedPfx {
+            private_key: Some(private_key),
+            certificate: Some(certificate),
+            chain,
+        };
+        parsed.select_leaf();
+        Ok(parsed)
+    }
+
+    /// Read a PEM file with proper error handling
+    fn read_file(path: &Path) -> Result<Vec<u8>, ConversionError> {
+        if !path.exists() {
+            return Err(ConversionError::FileNotFound(path.display().to_string()));
+        }
 
-        Ok(ParsedPfx::from(parsed))
+        fs::read(path).map_err(|e| ConversionError::FileRead(path.display().to_string(), e))
     }
 }
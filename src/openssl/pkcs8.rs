@@ -0,0 +1,244 @@
+use crate::error::ConversionError;
+use crate::openssl::der::{self, TAG_NULL, TAG_OCTET_STRING};
+use openssl::base64;
+use openssl::hash::MessageDigest;
+use openssl::pkcs5;
+use openssl::pkey::{PKey, PKeyRef, Private};
+use openssl::rand::rand_bytes;
+use openssl::symm::{self, Cipher};
+
+/// PBES2 (PKCS#5 v2.0 password-based encryption)
+const OID_PBES2: &str = "1.2.840.113549.1.5.13";
+/// PBKDF2 key derivation
+const OID_PBKDF2: &str = "1.2.840.113549.1.5.12";
+/// HMAC-SHA256, the PRF used with PBKDF2
+const OID_HMAC_SHA256: &str = "1.2.840.113549.2.9";
+/// scrypt key derivation (RFC 7914)
+const OID_SCRYPT: &str = "1.3.6.1.4.1.11591.4.11";
+/// AES-256 in CBC mode
+const OID_AES_256_CBC: &str = "2.16.840.1.101.3.4.1.42";
+
+/// Default PBKDF2-HMAC-SHA256 iteration count
+pub const DEFAULT_PBKDF2_ITERATIONS: u32 = 600_000;
+/// Largest scrypt cost OpenSSL will decrypt within its default 32 MiB memory limit
+///
+/// OpenSSL does not let readers of PKCS#8 files raise that limit, so a higher
+/// cost would produce keys that `openssl pkey`, web servers and forge itself
+/// cannot open.
+const MAX_SCRYPT_COST: u32 = 1 << 14;
+/// scrypt cost parameter (N) used for written keys: the highest readable one
+pub const DEFAULT_SCRYPT_COST: u32 = MAX_SCRYPT_COST;
+
+/// scrypt block size (r)
+const SCRYPT_BLOCK_SIZE: u64 = 8;
+/// scrypt parallelization parameter (p)
+const SCRYPT_PARALLELISM: u64 = 1;
+/// AES-256 key length in bytes
+const KEY_LEN: usize = 32;
+const SALT_LEN: usize = 16;
+const IV_LEN: usize = 16;
+
+/// Key derivation function used to encrypt a private key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyDerivation {
+    /// PBKDF2 with HMAC-SHA256 and the given iteration count
+    Pbkdf2 { iterations: u32 },
+    /// scrypt with the given cost parameter N (a power of two), r = 8 and p = 1
+    Scrypt { cost: u32 },
+}
+
+impl KeyDerivation {
+    fn validate(&self) -> Result<(), ConversionError> {
+        match *self {
+            KeyDerivation::Pbkdf2 { iterations: 0 } => Err(ConversionError::InvalidOption(
+                "PBKDF2 iterations must be at least 1".to_string(),
+            )),
+            KeyDerivation::Scrypt { cost } if cost < 2 || !cost.is_power_of_two() => {
+                Err(ConversionError::InvalidOption(format!(
+                    "scrypt cost must be a power of two greater than 1, got {cost}"
+                )))
+            }
+            KeyDerivation::Scrypt { cost } if cost > MAX_SCRYPT_COST => {
+                Err(ConversionError::InvalidOption(format!(
+                    "scrypt cost {cost} is too high: OpenSSL cannot decrypt keys with a cost \
+                     above {MAX_SCRYPT_COST} (32 MiB memory limit)"
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Settings for writing a private key as encrypted PKCS#8
+///
+/// Keys are encrypted with PBES2 using AES-256-CBC and the chosen key
+/// derivation function, producing an `ENCRYPTED PRIVATE KEY` PEM block.
+#[derive(Clone)]
+pub struct KeyEncryption {
+    /// Passphrase protecting the key
+    pub passphrase: String,
+    /// Key derivation function and its cost
+    pub kdf: KeyDerivation,
+}
+
+impl std::fmt::Debug for KeyEncryption {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KeyEncryption")
+            .field("passphrase", &"<redacted>")
+            .field("kdf", &self.kdf)
+            .finish()
+    }
+}
+
+impl KeyEncryption {
+    /// Encryption with PBKDF2-HMAC-SHA256 and the given iteration count
+    pub fn pbkdf2(passphrase: impl Into<String>, iterations: u32) -> Self {
+        Self {
+            passphrase: passphrase.into(),
+            kdf: KeyDerivation::Pbkdf2 { iterations },
+        }
+    }
+
+    /// Encryption with scrypt and the given cost parameter N
+    pub fn scrypt(passphrase: impl Into<String>, cost: u32) -> Self {
+        Self {
+            passphrase: passphrase.into(),
+            kdf: KeyDerivation::Scrypt { cost },
+        }
+    }
+
+    /// Describe the encryption, e.g. "AES-256-CBC, PBKDF2-HMAC-SHA256 (600000 iterations)"
+    pub fn description(&self) -> String {
+        match self.kdf {
+            KeyDerivation::Pbkdf2 { iterations } => {
+                format!("AES-256-CBC, PBKDF2-HMAC-SHA256 ({iterations} iterations)")
+            }
+            KeyDerivation::Scrypt { cost } => format!(
+                "AES-256-CBC, scrypt (N={cost}, r={SCRYPT_BLOCK_SIZE}, p={SCRYPT_PARALLELISM})"
+            ),
+        }
+    }
+}
+
+/// Encrypt a private key as a PKCS#8 `ENCRYPTED PRIVATE KEY` PEM block
+pub fn encrypt_to_pem(
+    key: &PKeyRef<Private>,
+    encryption: &KeyEncryption,
+) -> Result<Vec<u8>, ConversionError> {
+    let der = encrypt_to_der(key, encryption)?;
+    Ok(to_pem("ENCRYPTED PRIVATE KEY", &der))
+}
+
+/// Encrypt a private key as a DER encoded PKCS#8 `EncryptedPrivateKeyInfo`
+pub fn encrypt_to_der(
+    key: &PKeyRef<Private>,
+    encryption: &KeyEncryption,
+) -> Result<Vec<u8>, ConversionError> {
+    encryption.kdf.validate()?;
+    if encryption.passphrase.is_empty() {
+        return Err(ConversionError::PasswordInput(
+            "The private key passphrase must not be empty".to_string(),
+        ));
+    }
+
+    let mut salt = [0u8; SALT_LEN];
+    let mut iv = [0u8; IV_LEN];
+    rand_bytes(&mut salt)?;
+    rand_bytes(&mut iv)?;
+
+    let passphrase = encryption.passphrase.as_bytes();
+    let mut key_bytes = [0u8; KEY_LEN];
+    let kdf_algorithm = match encryption.kdf {
+        KeyDerivation::Pbkdf2 { iterations } => {
+            pkcs5::pbkdf2_hmac(
+                passphrase,
+                &salt,
+                iterations as usize,
+                MessageDigest::sha256(),
+                &mut key_bytes,
+            )?;
+            der::encode_sequence(&[
+                der::encode_oid(OID_PBKDF2),
+                der::encode_sequence(&[
+                    der::encode(TAG_OCTET_STRING, &salt),
+                    der::encode_integer(u64::from(iterations)),
+                    der::encode_integer(KEY_LEN as u64),
+                    der::encode_sequence(&[
+                        der::encode_oid(OID_HMAC_SHA256),
+                        der::encode(TAG_NULL, &[]),
+                    ]),
+                ]),
+            ])
+        }
+        KeyDerivation::Scrypt { cost } => {
+            let cost = u64::from(cost);
+            // Memory needed by OpenSSL's scrypt, with headroom for its bookkeeping
+            let max_memory = 128 * SCRYPT_BLOCK_SIZE * (cost + SCRYPT_PARALLELISM + 2) + (1 << 20);
+            pkcs5::scrypt(
+                passphrase,
+                &salt,
+                cost,
+                SCRYPT_BLOCK_SIZE,
+                SCRYPT_PARALLELISM,
+                max_memory,
+                &mut key_bytes,
+            )?;
+            der::encode_sequence(&[
+                der::encode_oid(OID_SCRYPT),
+                der::encode_sequence(&[
+                    der::encode(TAG_OCTET_STRING, &salt),
+                    der::encode_integer(cost),
+                    der::encode_integer(SCRYPT_BLOCK_SIZE),
+                    der::encode_integer(SCRYPT_PARALLELISM),
+                    der::encode_integer(KEY_LEN as u64),
+                ]),
+            ])
+        }
+    };
+
+    let private_key_info = key.private_key_to_pkcs8()?;
+    let encrypted = symm::encrypt(
+        Cipher::aes_256_cbc(),
+        &key_bytes,
+        Some(&iv),
+        &private_key_info,
+    )?;
+
+    // EncryptedPrivateKeyInfo with PBES2 parameters (RFC 5958, RFC 8018)
+    let encrypted_private_key_info = der::encode_sequence(&[
+        der::encode_sequence(&[
+            der::encode_oid(OID_PBES2),
+            der::encode_sequence(&[
+                kdf_algorithm,
+                der::encode_sequence(&[
+                    der::encode_oid(OID_AES_256_CBC),
+                    der::encode(TAG_OCTET_STRING, &iv),
+                ]),
+            ]),
+        ]),
+        der::encode(TAG_OCTET_STRING, &encrypted),
+    ]);
+
+    // Make sure OpenSSL can read back what we wrote before handing it out
+    PKey::private_key_from_pkcs8_passphrase(&encrypted_private_key_info, passphrase).map_err(
+        |e| {
+            ConversionError::InvalidFormat(format!(
+                "Generated encrypted private key is invalid: {e}"
+            ))
+        },
+    )?;
+
+    Ok(encrypted_private_key_info)
+}
+
+/// Wrap DER data in a PEM block with 64 character lines
+fn to_pem(label: &str, der: &[u8]) -> Vec<u8> {
+    let encoded = base64::encode_block(der);
+    let mut pem = format!("-----BEGIN {label}-----\n");
+    for line in encoded.as_bytes().chunks(64) {
+        pem.push_str(std::str::from_utf8(line).unwrap_or_default());
+        pem.push('\n');
+    }
+    pem.push_str(&format!("-----END {label}-----\n"));
+    pem.into_bytes()
+}
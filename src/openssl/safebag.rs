@@ -0,0 +1,290 @@
+use crate::error::ConversionError;
+use crate::openssl::der::{
+    DerReader, TAG_CONTEXT_0, TAG_INTEGER, TAG_OCTET_STRING, TAG_OID, TAG_SEQUENCE, TAG_SET, Tlv,
+    oid_name,
+};
+use openssl::pkey::{PKey, Private};
+
+const OID_DATA: &str = "1.2.840.113549.1.7.1";
+const OID_ENCRYPTED_DATA: &str = "1.2.840.113549.1.7.6";
+const OID_KEY_BAG: &str = "1.2.840.113549.1.12.10.1.1";
+const OID_SHROUDED_KEY_BAG: &str = "1.2.840.113549.1.12.10.1.2";
+const OID_SAFE_CONTENTS_BAG: &str = "1.2.840.113549.1.12.10.1.6";
+const OID_FRIENDLY_NAME: &str = "1.2.840.113549.1.9.20";
+const OID_PBES2: &str = "1.2.840.113549.1.5.13";
+const OID_PBKDF2: &str = "1.2.840.113549.1.5.12";
+const OID_HMAC_SHA1: &str = "1.2.840.113549.2.7";
+
+/// Password based encryption and integrity algorithms used by a PFX file
+///
+/// Only the parts readable without the password are covered: encrypted
+/// SafeContents, shrouded keys stored in plain SafeContents, and the MAC.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PfxEncryption {
+    /// Algorithms protecting encrypted SafeContents (usually the certificates)
+    pub content_algorithms: Vec<String>,
+    /// Algorithms protecting shrouded private keys
+    pub key_algorithms: Vec<String>,
+    /// MAC digest algorithm and iteration count, if the file has a MAC
+    pub mac: Option<String>,
+}
+
+impl PfxEncryption {
+    /// All distinct encryption algorithms, certificates first
+    pub fn algorithms(&self) -> Vec<&str> {
+        let mut algorithms: Vec<&str> = Vec::new();
+        for algorithm in self.content_algorithms.iter().chain(&self.key_algorithms) {
+            if !algorithms.contains(&algorithm.as_str()) {
+                algorithms.push(algorithm);
+            }
+        }
+        algorithms
+    }
+
+    /// Encryption algorithms that OpenSSL 3 only offers through its legacy provider
+    pub fn legacy_algorithms(&self) -> Vec<&str> {
+        self.algorithms()
+            .into_iter()
+            .filter(|name| is_legacy_algorithm(name))
+            .collect()
+    }
+
+    /// One-line description, e.g. for verbose output
+    pub fn summary(&self) -> String {
+        let join = |algorithms: &[String]| {
+            if algorithms.is_empty() {
+                "none".to_string()
+            } else {
+                algorithms.join(", ")
+            }
+        };
+        format!(
+            "certificates: {}; keys: {}; MAC: {}",
+            join(&self.content_algorithms),
+            join(&self.key_algorithms),
+            self.mac.as_deref().unwrap_or("none")
+        )
+    }
+}
+
+/// Check whether a PBE algorithm needs OpenSSL's legacy provider (RC2, RC4, single DES)
+pub fn is_legacy_algorithm(name: &str) -> bool {
+    name.contains("RC2") || name.contains("RC4") || name.contains("AndDES-CBC")
+}
+
+/// Read the encryption and MAC algorithms of a PFX without decrypting it
+pub fn read_encryption(data: &[u8]) -> Result<PfxEncryption, ConversionError> {
+    let mut pfx = DerReader::new(data).expect(TAG_SEQUENCE)?.reader();
+    pfx.expect(TAG_INTEGER)?;
+    let auth_safe = pfx.expect(TAG_SEQUENCE)?;
+    let auth_safe_data = content_info_data(auth_safe.content)?;
+
+    let mut encryption = PfxEncryption::default();
+    if let Some(mac_data) = pfx.read_optional(TAG_SEQUENCE)? {
+        encryption.mac = Some(read_mac(mac_data)?);
+    }
+
+    let mut content_infos = DerReader::new(&auth_safe_data)
+        .expect(TAG_SEQUENCE)?
+        .reader();
+    while !content_infos.is_empty() {
+        let content_info = content_infos.expect(TAG_SEQUENCE)?;
+        let mut fields = content_info.reader();
+        match fields.expect(TAG_OID)?.oid()?.as_str() {
+            OID_DATA => {
+                let safe_contents = content_info_data(content_info.content)?;
+                let mut bags = DerReader::new(&safe_contents)
+                    .expect(TAG_SEQUENCE)?
+                    .reader();
+                while !bags.is_empty() {
+                    let mut bag = bags.expect(TAG_SEQUENCE)?.reader();
+                    if bag.expect(TAG_OID)?.oid()? == OID_SHROUDED_KEY_BAG {
+                        let mut info = bag.expect(TAG_CONTEXT_0)?.reader().read()?.reader();
+                        let algorithm = algorithm_name(info.expect(TAG_SEQUENCE)?)?;
+                        encryption.key_algorithms.push(algorithm);
+                    }
+                }
+            }
+            OID_ENCRYPTED_DATA => {
+                let encrypted_data = fields.expect(TAG_CONTEXT_0)?;
+                let mut data = DerReader::new(encrypted_data.content)
+                    .expect(TAG_SEQUENCE)?
+                    .reader();
+                data.expect(TAG_INTEGER)?;
+                let mut content = data.expect(TAG_SEQUENCE)?.reader();
+                content.expect(TAG_OID)?;
+                let algorithm = algorithm_name(content.expect(TAG_SEQUENCE)?)?;
+                encryption.content_algorithms.push(algorithm);
+            }
+            _ => {}
+        }
+    }
+
+    Ok(encryption)
+}
+
+/// Describe the digest and iteration count of a PFX MacData structure
+fn read_mac(mac_data: Tlv<'_>) -> Result<String, ConversionError> {
+    let mut fields = mac_data.reader();
+    let mut digest_info = fields.expect(TAG_SEQUENCE)?.reader();
+    let mut digest_algorithm = digest_info.expect(TAG_SEQUENCE)?.reader();
+    let digest = oid_name(&digest_algorithm.expect(TAG_OID)?.oid()?);
+    fields.expect(TAG_OCTET_STRING)?;
+    let iterations = match fields.read_optional(TAG_INTEGER)? {
+        Some(iterations) => iterations.integer()?,
+        None => 1,
+    };
+    Ok(format!("{digest}, {iterations} iterations"))
+}
+
+/// Describe a PBE AlgorithmIdentifier, looking inside PBES2 parameters
+fn algorithm_name(algorithm: Tlv<'_>) -> Result<String, ConversionError> {
+    let mut fields = algorithm.reader();
+    let oid = fields.expect(TAG_OID)?.oid()?;
+    if oid != OID_PBES2 {
+        return Ok(oid_name(&oid));
+    }
+
+    // PBES2-params ::= SEQUENCE { keyDerivationFunc, encryptionScheme }
+    let mut params = fields.expect(TAG_SEQUENCE)?.reader();
+    let mut kdf = params.expect(TAG_SEQUENCE)?.reader();
+    let kdf_oid = kdf.expect(TAG_OID)?.oid()?;
+    let mut kdf_name = oid_name(&kdf_oid);
+    if kdf_oid == OID_PBKDF2 {
+        // PBKDF2-params ::= SEQUENCE { salt, iterationCount, keyLength OPTIONAL, prf DEFAULT hmacWithSHA1 }
+        let mut kdf_params = kdf.expect(TAG_SEQUENCE)?.reader();
+        kdf_params.read()?;
+        kdf_params.expect(TAG_INTEGER)?;
+        kdf_params.read_optional(TAG_INTEGER)?;
+        let prf = match kdf_params.read_optional(TAG_SEQUENCE)? {
+            Some(prf) => prf.reader().expect(TAG_OID)?.oid()?,
+            None => OID_HMAC_SHA1.to_string(),
+        };
+        kdf_name = format!("{kdf_name} with {}", oid_name(&prf));
+    }
+    let cipher = oid_name(
+        &params
+            .expect(TAG_SEQUENCE)?
+            .reader()
+            .expect(TAG_OID)?
+            .oid()?,
+    );
+
+    Ok(format!("PBES2 ({kdf_name}, {cipher})"))
+}
+
+/// A private key SafeBag from the unencrypted SafeContents of a PFX
+#[derive(Debug)]
+pub struct KeyBag {
+    /// The decoded key, `None` for the first key bag, which `Pkcs12::parse2` returns
+    pub key: Option<PKey<Private>>,
+    pub friendly_name: Option<String>,
+}
+
+/// Decode every private key SafeBag stored in the unencrypted SafeContents of a PFX
+///
+/// `PKCS12_parse` only decrypts the first key bag it meets, so that bag is
+/// returned without its key and every other key is decrypted here, once, by
+/// OpenSSL's PKCS#8 reader, which wipes its intermediate buffers. Encrypted
+/// SafeContents are skipped: writers keep certificates there, and those come
+/// from `Pkcs12::parse2` already decrypted.
+///
+/// The password is expected to have been verified already (e.g. by `Pkcs12::parse2`).
+pub fn read_key_bags(data: &[u8], password: &str) -> Result<Vec<KeyBag>, ConversionError> {
+    let mut pfx = DerReader::new(data).expect(TAG_SEQUENCE)?.reader();
+    pfx.expect(TAG_INTEGER)?;
+    let auth_safe = pfx.expect(TAG_SEQUENCE)?;
+    let auth_safe_data = content_info_data(auth_safe.content)?;
+
+    let mut bags = Vec::new();
+    let mut content_infos = DerReader::new(&auth_safe_data)
+        .expect(TAG_SEQUENCE)?
+        .reader();
+    while !content_infos.is_empty() {
+        let content_info = content_infos.expect(TAG_SEQUENCE)?;
+        let mut fields = content_info.reader();
+        match fields.expect(TAG_OID)?.oid()?.as_str() {
+            OID_DATA => {
+                let safe_contents = content_info_data(content_info.content)?;
+                read_safe_contents(&safe_contents, password, &mut bags)?;
+            }
+            OID_ENCRYPTED_DATA => {}
+            other => {
+                return Err(ConversionError::InvalidFormat(format!(
+                    "Unsupported PKCS#12 content type: {other}"
+                )));
+            }
+        }
+    }
+
+    Ok(bags)
+}
+
+/// Extract the OCTET STRING payload of a `data` ContentInfo
+fn content_info_data(content_info: &[u8]) -> Result<Vec<u8>, ConversionError> {
+    let mut fields = DerReader::new(content_info);
+    let content_type = fields.expect(TAG_OID)?.oid()?;
+    if content_type != OID_DATA {
+        return Err(ConversionError::InvalidFormat(format!(
+            "Expected PKCS#7 data content, found {content_type}"
+        )));
+    }
+    let explicit = fields.expect(TAG_CONTEXT_0)?;
+    explicit.reader().read()?.octets()
+}
+
+/// Decode the key bags of a SafeContents structure, recursing into nested safeContentsBags
+fn read_safe_contents(
+    safe_contents: &[u8],
+    password: &str,
+    bags: &mut Vec<KeyBag>,
+) -> Result<(), ConversionError> {
+    let mut reader = DerReader::new(safe_contents).expect(TAG_SEQUENCE)?.reader();
+    while !reader.is_empty() {
+        let mut fields = reader.expect(TAG_SEQUENCE)?.reader();
+        let bag_id = fields.expect(TAG_OID)?.oid()?;
+        let bag_value = fields.expect(TAG_CONTEXT_0)?.reader().read()?;
+
+        let shrouded = match bag_id.as_str() {
+            OID_KEY_BAG => false,
+            OID_SHROUDED_KEY_BAG => true,
+            OID_SAFE_CONTENTS_BAG => {
+                read_safe_contents(bag_value.raw, password, bags)?;
+                continue;
+            }
+            _ => continue,
+        };
+
+        let key = if bags.is_empty() {
+            None
+        } else if shrouded {
+            Some(PKey::private_key_from_pkcs8_passphrase(
+                bag_value.raw,
+                password.as_bytes(),
+            )?)
+        } else {
+            Some(PKey::private_key_from_pkcs8(bag_value.raw)?)
+        };
+        let friendly_name = match fields.read_optional(TAG_SET)? {
+            Some(attributes) => read_friendly_name(attributes.content)?,
+            None => None,
+        };
+        bags.push(KeyBag { key, friendly_name });
+    }
+
+    Ok(())
+}
+
+/// Read the friendlyName bag attribute
+fn read_friendly_name(attributes: &[u8]) -> Result<Option<String>, ConversionError> {
+    let mut reader = DerReader::new(attributes);
+    while !reader.is_empty() {
+        let mut attribute = reader.expect(TAG_SEQUENCE)?.reader();
+        let attr_id = attribute.expect(TAG_OID)?.oid()?;
+        let mut values = attribute.expect(TAG_SET)?.reader();
+        if attr_id == OID_FRIENDLY_NAME && !values.is_empty() {
+            return Ok(Some(values.read()?.bmp_string()?));
+        }
+    }
+    Ok(None)
+}
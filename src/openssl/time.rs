@@ -0,0 +1,174 @@
+use openssl::asn1::{Asn1Time, Asn1TimeRef};
+use std::fmt;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Length of a day, for turning validity periods into whole days
+pub const SECONDS_PER_DAY: i64 = 86_400;
+
+/// A point in time, in whole seconds since the Unix epoch
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Timestamp(i64);
+
+impl Timestamp {
+    pub fn from_unix(seconds: i64) -> Self {
+        Self(seconds)
+    }
+
+    /// The current time
+    pub fn now() -> Self {
+        Self(
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_secs() as i64),
+        )
+    }
+
+    /// Convert an ASN.1 time, such as a certificate's notAfter
+    ///
+    /// Returns `None` when OpenSSL cannot interpret the time.
+    pub fn from_asn1(time: &Asn1TimeRef) -> Option<Self> {
+        Asn1Time::from_unix(0)
+            .and_then(|epoch| epoch.diff(time))
+            .map(|diff| Self(i64::from(diff.days) * SECONDS_PER_DAY + i64::from(diff.secs)))
+            .ok()
+    }
+
+    /// Seconds since the Unix epoch
+    pub fn unix_seconds(self) -> i64 {
+        self.0
+    }
+
+    /// Write the time as ISO 8601, e.g. `2026-01-01T00:00:00Z` or `2026-01-01T01:00:00+01:00`
+    pub fn format(self, zone: TimeZone) -> String {
+        let offset = zone.offset_at(self.0);
+        let local = self.0 + i64::from(offset);
+        let (year, month, day) = civil_from_days(local.div_euclid(SECONDS_PER_DAY));
+        let seconds = local.rem_euclid(SECONDS_PER_DAY);
+        let zone = match offset {
+            0 if zone == TimeZone::Utc => "Z".to_string(),
+            _ => {
+                let sign = if offset < 0 { '-' } else { '+' };
+                let minutes = offset.unsigned_abs() / 60;
+                format!("{sign}{:02}:{:02}", minutes / 60, minutes % 60)
+            }
+        };
+        format!(
+            "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}{zone}",
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        )
+    }
+}
+
+/// Write a date that may not have been readable, e.g. `unknown`
+pub fn format_date(date: Option<Timestamp>, zone: TimeZone) -> String {
+    date.map_or_else(|| "unknown".to_string(), |date| date.format(zone))
+}
+
+impl fmt::Display for Timestamp {
+    /// Write the time as ISO 8601 in UTC
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.format(TimeZone::Utc))
+    }
+}
+
+/// Time zone used to display dates
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TimeZone {
+    #[default]
+    Utc,
+    /// The zone of the machine forge runs on
+    Local,
+    /// A fixed offset east of UTC, in seconds
+    Offset(i32),
+}
+
+impl TimeZone {
+    /// Offset from UTC in seconds at a point in time
+    pub fn offset_at(self, seconds: i64) -> i32 {
+        match self {
+            TimeZone::Utc => 0,
+            TimeZone::Local => local_offset(seconds),
+            TimeZone::Offset(offset) => offset,
+        }
+    }
+}
+
+impl FromStr for TimeZone {
+    type Err = String;
+
+    /// Parse `UTC`, `local` or an offset such as `+02:00`, `-0530` or `+01`
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if value.eq_ignore_ascii_case("utc") || value.eq_ignore_ascii_case("z") {
+            return Ok(TimeZone::Utc);
+        }
+        if value.eq_ignore_ascii_case("local") {
+            return if cfg!(unix) {
+                Ok(TimeZone::Local)
+            } else {
+                Err("the local time zone is only supported on Unix platforms; \
+                     use UTC or an offset such as +02:00"
+                    .to_string())
+            };
+        }
+
+        let invalid = || format!("'{value}' is not UTC, local or an offset such as +02:00");
+        let (sign, digits) = match value.as_bytes().first() {
+            Some(b'+') => (1, &value[1..]),
+            Some(b'-') => (-1, &value[1..]),
+            _ => return Err(invalid()),
+        };
+        let digits = digits.replacen(':', "", 1);
+        if !matches!(digits.len(), 2 | 4) || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(invalid());
+        }
+        let hours: i32 = digits[..2].parse().map_err(|_| invalid())?;
+        let minutes: i32 = digits[2..].parse().unwrap_or(0);
+        if hours > 14 || minutes > 59 {
+            return Err(invalid());
+        }
+        Ok(TimeZone::Offset(sign * (hours * 3600 + minutes * 60)))
+    }
+}
+
+#[cfg(unix)]
+fn local_offset(seconds: i64) -> i32 {
+    let time = seconds as libc::time_t;
+    // SAFETY: tm is plain data, and localtime_r only writes to the struct we pass
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    let result = unsafe { libc::localtime_r(&time, &mut tm) };
+    if result.is_null() {
+        0
+    } else {
+        tm.tm_gmtoff as i32
+    }
+}
+
+/// `local` is rejected when parsing on these platforms
+#[cfg(not(unix))]
+fn local_offset(_seconds: i64) -> i32 {
+    0
+}
+
+/// Convert days since the Unix epoch to a proleptic Gregorian (year, month, day)
+///
+/// This is Howard Hinnant's `civil_from_days` algorithm.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month as u32, day as u32)
+}
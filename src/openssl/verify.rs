@@ -0,0 +1,153 @@
+use crate::error::ConversionError;
+use crate::openssl::{CertificateInfo, DistinguishedName, ParsedPfx};
+use openssl::ssl::SslFiletype;
+use openssl::stack::Stack;
+use openssl::x509::store::{X509Lookup, X509Store, X509StoreBuilder, X509StoreRef};
+use openssl::x509::{X509, X509StoreContext, X509VerifyResult};
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+
+/// Certificates trusted when verifying a chain
+///
+/// Without a CA file or directory, OpenSSL's default (system) store is used.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TrustStore {
+    /// PEM bundle of trusted certificates
+    pub ca_file: Option<PathBuf>,
+    /// Directory of trusted certificates named by subject hash (`openssl rehash`)
+    pub ca_dir: Option<PathBuf>,
+}
+
+impl fmt::Display for TrustStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.ca_file, &self.ca_dir) {
+            (None, None) => write!(f, "the system trust store"),
+            (Some(file), None) => write!(f, "CA file '{}'", file.display()),
+            (None, Some(dir)) => write!(f, "CA directory '{}'", dir.display()),
+            (Some(file), Some(dir)) => write!(
+                f,
+                "CA file '{}' and CA directory '{}'",
+                file.display(),
+                dir.display()
+            ),
+        }
+    }
+}
+
+impl TrustStore {
+    /// Load the trusted certificates into an OpenSSL store
+    pub fn load(&self) -> Result<X509Store, ConversionError> {
+        let mut builder = X509StoreBuilder::new()?;
+
+        if self.ca_file.is_none() && self.ca_dir.is_none() {
+            builder.set_default_paths()?;
+        }
+
+        if let Some(ref path) = self.ca_file {
+            let pem = fs::read(path)
+                .map_err(|e| ConversionError::FileRead(path.display().to_string(), e))?;
+            let certs = X509::stack_from_pem(&pem).map_err(|e| {
+                ConversionError::InvalidPem(format!(
+                    "Failed to parse CA file '{}': {e}",
+                    path.display()
+                ))
+            })?;
+            if certs.is_empty() {
+                return Err(ConversionError::InvalidPem(format!(
+                    "CA file '{}' contains no certificates",
+                    path.display()
+                )));
+            }
+            for cert in certs {
+                builder.add_cert(cert)?;
+            }
+        }
+
+        if let Some(ref path) = self.ca_dir {
+            if !path.is_dir() {
+                return Err(ConversionError::FileNotFound(path.display().to_string()));
+            }
+            builder
+                .add_lookup(X509Lookup::hash_dir())?
+                .add_dir(&path.to_string_lossy(), SslFiletype::PEM)?;
+        }
+
+        Ok(builder.build())
+    }
+}
+
+/// One certificate of the chain OpenSSL built during verification
+#[derive(Debug, Clone)]
+pub struct ChainStep {
+    /// Position in the chain, 0 being the main certificate
+    pub depth: usize,
+    /// Subject of the certificate
+    pub subject: DistinguishedName,
+    /// Issuer of the certificate
+    pub issuer: DistinguishedName,
+}
+
+/// Outcome of verifying a certificate chain
+#[derive(Debug, Clone)]
+pub struct Verification {
+    /// The chain as far as OpenSSL could build it, from the main certificate up
+    pub steps: Vec<ChainStep>,
+    /// Why verification failed (e.g. "certificate has expired"), if it did
+    pub failure: Option<String>,
+    /// Depth of the certificate that failed verification
+    pub failure_depth: Option<usize>,
+}
+
+impl Verification {
+    /// Check whether the chain leads to a trusted root
+    pub fn is_trusted(&self) -> bool {
+        self.failure.is_none()
+    }
+}
+
+impl ParsedPfx {
+    /// Verify the main certificate against a trust store
+    ///
+    /// The chain certificates are offered as untrusted intermediates; only the
+    /// store decides which roots are trusted.
+    pub fn verify(&self, store: &X509StoreRef) -> Result<Verification, ConversionError> {
+        let certificate = self.require_certificate()?;
+        let mut untrusted = Stack::new()?;
+        for cert in &self.chain {
+            untrusted.push(cert.clone())?;
+        }
+
+        let mut context = X509StoreContext::new()?;
+        let verification = context.init(store, certificate, &untrusted, |context| {
+            let trusted = context.verify_cert()?;
+            let steps = context
+                .chain()
+                .map(|chain| {
+                    chain
+                        .iter()
+                        .enumerate()
+                        .map(|(depth, cert)| {
+                            let info = CertificateInfo::from_x509(&cert.to_owned());
+                            ChainStep {
+                                depth,
+                                subject: info.subject_name,
+                                issuer: info.issuer_name,
+                            }
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+            let error = context.error();
+            let failed = !trusted || error != X509VerifyResult::OK;
+
+            Ok(Verification {
+                steps,
+                failure: failed.then(|| error.error_string().to_string()),
+                failure_depth: failed.then(|| context.error_depth() as usize),
+            })
+        })?;
+
+        Ok(verification)
+    }
+}
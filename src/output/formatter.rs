@@ -1,6 +1,8 @@
-use crate::cli::Args;
-use crate::openssl::ParsedPfx;
-use crate::output::OutputConfig;
+use crate::openssl::{
+    CertificateDetails, CertificateExpiry, ExpiryStatus, ExpiryThresholds, KeyOutput, LintFinding,
+    OutputEncoding, ParsedPfx, PfxIdentity, Severity, Timestamp, format_date, key_type_name,
+};
+use crate::output::{BatchEntry, OutputConfig, WrittenFile};
 use colored::*;
 use console::Term;
 use std::io::{self, Write};
@@ -26,6 +28,28 @@ struct FileOutput {
     status: String,
 }
 
+#[derive(Tabled)]
+struct BatchOutput {
+    #[tabled(rename = "Input")]
+    input: String,
+    #[tabled(rename = "Output Directory")]
+    output_dir: String,
+    #[tabled(rename = "Result")]
+    result: String,
+}
+
+#[derive(Tabled)]
+struct FindingOutput {
+    #[tabled(rename = "Severity")]
+    severity: String,
+    #[tabled(rename = "Rule")]
+    rule: String,
+    #[tabled(rename = "Certificate")]
+    certificate: String,
+    #[tabled(rename = "Finding")]
+    message: String,
+}
+
 #[derive(Tabled)]
 struct CertInfo {
     #[tabled(rename = "Property")]
@@ -44,77 +68,38 @@ impl OutputFormatter {
     /// Print a beautifully formatted summary
     pub fn print_summary(
         &self,
-        args: &Args,
         parsed: &ParsedPfx,
+        key_output: &KeyOutput,
+        encoding: OutputEncoding,
+        written: &[WrittenFile],
+        findings: &[LintFinding],
         term: &mut Term,
     ) -> io::Result<()> {
         // Header
         self.print_header("Conversion Summary", term)?;
 
         // Create table data
-        let mut files = vec![
-            FileOutput {
-                file_type: "Private Key".to_string(),
-                filename: args.key_filename().to_string(),
-                location: args.output_dir().to_string(),
-                status: if self.config.use_colors {
-                    "✓ Created".green().to_string()
-                } else {
-                    "✓ Created".to_string()
-                },
-            },
-            FileOutput {
-                file_type: "Certificate".to_string(),
-                filename: args.cert_filename().to_string(),
-                location: args.output_dir().to_string(),
+        let files: Vec<FileOutput> = written
+            .iter()
+            .map(|file| FileOutput {
+                file_type: file.file_type.clone(),
+                filename: file
+                    .path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+                location: file
+                    .path
+                    .parent()
+                    .map(|dir| dir.display().to_string())
+                    .unwrap_or_default(),
                 status: if self.config.use_colors {
                     "✓ Created".green().to_string()
                 } else {
                     "✓ Created".to_string()
                 },
-            },
-        ];
-
-        // Add combined file if requested
-        if args.combined {
-            files.push(FileOutput {
-                file_type: "Combined PEM".to_string(),
-                filename: args.combined_filename().to_string(),
-                location: args.output_dir().to_string(),
-                status: if self.config.use_colors {
-                    "✓ Created".green().to_string()
-                } else {
-                    "✓ Created".to_string()
-                },
-            });
-        }
-
-        // Add chain files if requested
-        if args.chain && parsed.has_chain() {
-            files.push(FileOutput {
-                file_type: "Certificate Chain".to_string(),
-                filename: "certificate_chain.pem".to_string(),
-                location: args.output_dir().to_string(),
-                status: if self.config.use_colors {
-                    "✓ Created".green().to_string()
-                } else {
-                    "✓ Created".to_string()
-                },
-            });
-
-            for i in 0..parsed.chain_length() {
-                files.push(FileOutput {
-                    file_type: format!("Chain Cert {}", i + 1),
-                    filename: format!("chain_cert_{}.pem", i + 1),
-                    location: args.output_dir().to_string(),
-                    status: if self.config.use_colors {
-                        "✓ Created".green().to_string()
-                    } else {
-                        "✓ Created".to_string()
-                    },
-                });
-            }
-        }
+            })
+            .collect();
 
         // Create and style the table
         let mut table = Table::new(&files);
@@ -128,8 +113,12 @@ impl OutputFormatter {
             writeln!(term, "{table}")?;
         }
 
+        if !findings.is_empty() {
+            self.print_findings(findings, term)?;
+        }
+
         // Statistics box
-        self.print_stats_box(parsed, term)?;
+        self.print_stats_box(parsed, key_output, encoding, written.len(), term)?;
 
         // Footer
         if self.config.use_colors {
@@ -145,20 +134,114 @@ impl OutputFormatter {
         Ok(())
     }
 
+    /// Print one row per input of a batch conversion and the overall result
+    pub fn print_batch_summary(&self, entries: &[BatchEntry], term: &mut Term) -> io::Result<()> {
+        self.print_header("Batch Summary", term)?;
+
+        let rows: Vec<BatchOutput> = entries
+            .iter()
+            .map(|entry| {
+                let result = match entry.result {
+                    Ok(1) => "✓ 1 file".to_string(),
+                    Ok(count) => format!("✓ {count} files"),
+                    Err(ref e) => format!("✗ {e}"),
+                };
+                BatchOutput {
+                    input: entry.input.display().to_string(),
+                    output_dir: entry
+                        .output_dir
+                        .as_ref()
+                        .map_or_else(|| "-".to_string(), |dir| dir.display().to_string()),
+                    result: match (entry.result.is_ok(), self.config.use_colors) {
+                        (true, true) => result.green().to_string(),
+                        (false, true) => result.red().to_string(),
+                        (_, false) => result,
+                    },
+                }
+            })
+            .collect();
+
+        let mut table = Table::new(&rows);
+        table
+            .with(Style::rounded())
+            .with(Modify::new(Rows::first()).with(Alignment::center()));
+
+        if self.config.use_colors {
+            writeln!(term, "{}", table.to_string().bright_white())?;
+        } else {
+            writeln!(term, "{table}")?;
+        }
+
+        let failed = entries.iter().filter(|entry| entry.result.is_err()).count();
+        let converted = entries.len() - failed;
+        match (failed, self.config.use_colors) {
+            (0, true) => writeln!(
+                term,
+                "\n{} Converted all {converted} PFX files successfully!",
+                "🎉".bright_green()
+            )?,
+            (0, false) => writeln!(
+                term,
+                "\n✓ Converted all {converted} PFX files successfully!"
+            )?,
+            (_, true) => writeln!(
+                term,
+                "\n{} Converted {converted} of {} PFX files; {failed} failed",
+                "✗".bright_red(),
+                entries.len()
+            )?,
+            (_, false) => writeln!(
+                term,
+                "\n✗ Converted {converted} of {} PFX files; {failed} failed",
+                entries.len()
+            )?,
+        }
+
+        Ok(())
+    }
+
+    /// Print the security lint findings as a table
+    pub fn print_findings(&self, findings: &[LintFinding], term: &mut Term) -> io::Result<()> {
+        self.print_header("Security Findings", term)?;
+
+        let rows: Vec<FindingOutput> = findings
+            .iter()
+            .map(|finding| FindingOutput {
+                severity: match (finding.severity, self.config.use_colors) {
+                    (Severity::Error, true) => "✗ error".bright_red().to_string(),
+                    (Severity::Warning, true) => "⚠ warning".yellow().to_string(),
+                    (Severity::Error, false) => "✗ error".to_string(),
+                    (Severity::Warning, false) => "! warning".to_string(),
+                },
+                rule: finding.rule.clone(),
+                certificate: finding.certificate.clone(),
+                message: finding.message.clone(),
+            })
+            .collect();
+
+        let mut table = Table::new(&rows);
+        table.with(Style::rounded());
+        writeln!(term, "{table}")
+    }
+
     /// Print certificate information in a formatted way
     pub fn print_cert_info(&self, parsed: &ParsedPfx, term: &mut Term) -> io::Result<()> {
         self.print_header("Certificate Information", term)?;
 
-        let cert_info = parsed.certificate_info();
+        let Some(cert_info) = parsed.certificate_info() else {
+            writeln!(term, "No certificate associated with a private key")?;
+            return Ok(());
+        };
+        let expiry = CertificateExpiry::new(&cert_info, true, Timestamp::now());
 
         let cert_data = vec![
             CertInfo {
                 property: "Subject".to_string(),
-                value: cert_info.subject,
+                value: cert_info.subject_name.format(self.config.name_format),
             },
             CertInfo {
                 property: "Issuer".to_string(),
-                value: cert_info.issuer,
+                value: cert_info.issuer_name.format(self.config.name_format),
             },
             CertInfo {
                 property: "Serial Number".to_string(),
@@ -166,11 +249,15 @@ impl OutputFormatter {
             },
             CertInfo {
                 property: "Valid From".to_string(),
-                value: cert_info.not_before,
+                value: format_date(cert_info.not_before, self.config.time_zone),
             },
             CertInfo {
                 property: "Valid Until".to_string(),
-                value: cert_info.not_after,
+                value: format_date(cert_info.not_after, self.config.time_zone),
+            },
+            CertInfo {
+                property: "Expires".to_string(),
+                value: expiry.relative(),
             },
             CertInfo {
                 property: "Signature Algorithm".to_string(),
@@ -184,7 +271,143 @@ impl OutputFormatter {
             .with(Modify::new(Rows::first()).with(Alignment::center()));
 
         if self.config.use_colors {
-            writeln!(term, "{}", table.to_string().bright_white())?;
+            let table = color_expiry(table.to_string(), &expiry);
+            writeln!(term, "{}", table.bright_white())?;
+        } else {
+            writeln!(term, "{table}")?;
+        }
+
+        Ok(())
+    }
+
+    /// Print the full X.509 details of every certificate, one table each
+    pub fn print_inspection(&self, identities: &[PfxIdentity], term: &mut Term) -> io::Result<()> {
+        for identity in identities {
+            let parsed = &identity.contents;
+            if let Some(ref name) = identity.friendly_name {
+                writeln!(term, "\nIdentity: {name}")?;
+            }
+            match parsed.private_key {
+                Some(ref key) => writeln!(
+                    term,
+                    "Private key: {} ({} bits)",
+                    key_type_name(key.id()),
+                    key.bits()
+                )?,
+                None => writeln!(term, "Private key: none")?,
+            }
+
+            if let Some(ref cert) = parsed.certificate {
+                let details = CertificateDetails::from_x509(cert);
+                self.print_certificate_details("Certificate", &details, term)?;
+            }
+            for (index, cert) in parsed.chain.iter().enumerate() {
+                let details = CertificateDetails::from_x509(cert);
+                let title = format!("Chain Certificate {}", index + 1);
+                self.print_certificate_details(&title, &details, term)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Print one certificate's details as a property table
+    fn print_certificate_details(
+        &self,
+        title: &str,
+        details: &CertificateDetails,
+        term: &mut Term,
+    ) -> io::Result<()> {
+        self.print_header(title, term)?;
+
+        let info = &details.info;
+        let expiry = CertificateExpiry::new(info, true, Timestamp::now());
+        let mut rows = vec![
+            ("Subject", info.subject_name.format(self.config.name_format)),
+            ("Issuer", info.issuer_name.format(self.config.name_format)),
+            ("Version", details.version.to_string()),
+            ("Serial Number", info.serial_number.clone()),
+            (
+                "Valid From",
+                format_date(info.not_before, self.config.time_zone),
+            ),
+            (
+                "Valid Until",
+                format_date(info.not_after, self.config.time_zone),
+            ),
+            ("Expires", expiry.relative()),
+            ("Signature Algorithm", info.signature_algorithm.clone()),
+        ];
+
+        let public_key = match details.public_key_curve {
+            Some(ref curve) => format!(
+                "{} {} bits ({curve})",
+                details.public_key_algorithm, details.public_key_bits
+            ),
+            None => format!(
+                "{} {} bits",
+                details.public_key_algorithm, details.public_key_bits
+            ),
+        };
+        rows.push(("Public Key", public_key));
+
+        let lists = [
+            ("Subject Alt Names", &details.subject_alt_names),
+            ("Key Usage", &details.key_usage),
+            ("Extended Key Usage", &details.extended_key_usage),
+        ];
+        for (property, values) in lists {
+            if !values.is_empty() {
+                rows.push((property, values.join("\n")));
+            }
+        }
+
+        if let Some(constraints) = details.basic_constraints {
+            let value = match (constraints.ca, constraints.path_length) {
+                (true, Some(length)) => format!("CA, path length {length}"),
+                (true, None) => "CA".to_string(),
+                (false, _) => "Not a CA".to_string(),
+            };
+            rows.push(("Basic Constraints", value));
+        }
+        if let Some(ref id) = details.authority_key_id {
+            rows.push(("Authority Key ID", id.clone()));
+        }
+        if let Some(ref id) = details.subject_key_id {
+            rows.push(("Subject Key ID", id.clone()));
+        }
+
+        let lists = [
+            ("OCSP", &details.ocsp_urls),
+            ("CA Issuers", &details.ca_issuer_urls),
+            ("CRL Distribution", &details.crl_urls),
+            ("Policies", &details.policies),
+        ];
+        for (property, values) in lists {
+            if !values.is_empty() {
+                rows.push((property, values.join("\n")));
+            }
+        }
+
+        rows.push(("SHA-1 Fingerprint", details.sha1_fingerprint.clone()));
+        rows.push(("SHA-256 Fingerprint", details.sha256_fingerprint.clone()));
+
+        let cert_data: Vec<CertInfo> = rows
+            .into_iter()
+            .map(|(property, value)| CertInfo {
+                property: property.to_string(),
+                value,
+            })
+            .collect();
+
+        let mut table = Table::new(&cert_data);
+        table
+            .with(Style::rounded())
+            .with(Modify::new(Rows::first()).with(Alignment::center()));
+
+        if self.config.use_colors {
+            let table = color_expiry(table.to_string(), &expiry);
+            writeln!(term, "{}", table.bright_white())?;
         } else {
             writeln!(term, "{table}")?;
         }
@@ -224,9 +447,27 @@ impl OutputFormatter {
     }
 
     /// Print statistics in a box
-    fn print_stats_box(&self, parsed: &ParsedPfx, term: &mut Term) -> io::Result<()> {
+    fn print_stats_box(
+        &self,
+        parsed: &ParsedPfx,
+        key_output: &KeyOutput,
+        encoding: OutputEncoding,
+        file_count: usize,
+        term: &mut Term,
+    ) -> io::Result<()> {
         writeln!(term)?;
 
+        let cert_count = parsed.chain_length() + usize::from(parsed.has_certificate());
+        let chain_text = if parsed.has_chain() {
+            format!("{cert_count} certificates")
+        } else {
+            "No chain".to_string()
+        };
+        let key_text = match parsed.private_key {
+            Some(ref key) => key_output.description(key, encoding),
+            None => "No private key".to_string(),
+        };
+
         if self.config.use_colors {
             writeln!(
                 term,
@@ -237,7 +478,7 @@ impl OutputFormatter {
                 term,
                 "{} Files generated: {}                        {}",
                 "│".bright_blue(),
-                if parsed.has_chain() { "5+" } else { "2-3" }.bright_yellow(),
+                file_count.to_string().bright_yellow(),
                 "│".bright_blue()
             )?;
             writeln!(
@@ -245,9 +486,9 @@ impl OutputFormatter {
                 "{} Certificate chain: {}                      {}",
                 "│".bright_blue(),
                 if parsed.has_chain() {
-                    format!("{} certificates", parsed.chain_length() + 1).bright_green()
+                    chain_text.bright_green()
                 } else {
-                    "No chain".bright_red()
+                    chain_text.bright_red()
                 },
                 "│".bright_blue()
             )?;
@@ -255,7 +496,11 @@ impl OutputFormatter {
                 term,
                 "{} Private key format: {}                     {}",
                 "│".bright_blue(),
-                "PKCS#8 PEM".bright_green(),
+                if parsed.has_private_key() {
+                    key_text.bright_green()
+                } else {
+                    key_text.bright_red()
+                },
                 "│".bright_blue()
             )?;
             writeln!(
@@ -267,22 +512,30 @@ impl OutputFormatter {
             writeln!(term, "┌─ Statistics ─────────────────────────────────┐")?;
             writeln!(
                 term,
-                "│ Files generated: {}                        │",
-                if parsed.has_chain() { "5+" } else { "2-3" }
+                "│ Files generated: {file_count}                        │"
             )?;
             writeln!(
                 term,
-                "│ Certificate chain: {}                      │",
-                if parsed.has_chain() {
-                    format!("{} certificates", parsed.chain_length() + 1)
-                } else {
-                    "No chain".to_string()
-                }
+                "│ Certificate chain: {chain_text}                      │"
             )?;
-            writeln!(term, "│ Private key format: PKCS#8 PEM             │")?;
+            writeln!(term, "│ Private key format: {key_text}             │")?;
             writeln!(term, "└─────────────────────────────────────────────┘")?;
         }
 
         Ok(())
     }
 }
+
+/// Color the remaining validity in a rendered table by urgency
+///
+/// The text is colored after rendering, as escape codes would throw off the
+/// column widths.
+fn color_expiry(table: String, expiry: &CertificateExpiry) -> String {
+    let text = expiry.relative();
+    let colored = match ExpiryThresholds::default().expiry_status(expiry) {
+        ExpiryStatus::Ok => text.bright_green(),
+        ExpiryStatus::Warning => text.bright_yellow(),
+        ExpiryStatus::Critical | ExpiryStatus::Unknown => text.bright_red(),
+    };
+    table.replacen(&text, &colored.to_string(), 1)
+}
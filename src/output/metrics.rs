@@ -0,0 +1,113 @@
+use crate::openssl::{CertificateInfo, ParsedPfx, Timestamp};
+
+/// Metrics of one main certificate, for node_exporter's textfile collector
+#[derive(Debug, Clone)]
+pub struct CertificateMetrics {
+    /// Input path as given (`-` for stdin)
+    pub path: String,
+    /// Common name of the certificate subject, empty without one
+    pub common_name: String,
+    /// Serial number in hex
+    pub serial: String,
+    /// notAfter as seconds since the Unix epoch, `None` if it cannot be read
+    pub expiry_timestamp: Option<i64>,
+    /// Seconds until notAfter, negative once expired
+    pub seconds_left: Option<i64>,
+    /// Number of chain certificates stored with the certificate
+    pub chain_length: usize,
+    /// Size of the certificate's public key in bits
+    pub key_bits: u32,
+    /// Outcome of chain verification, when it ran
+    pub verified: Option<bool>,
+}
+
+impl CertificateMetrics {
+    /// Collect the metrics of a PFX identity; `None` without a main certificate
+    pub fn new(path: &str, parsed: &ParsedPfx, verified: Option<bool>) -> Option<Self> {
+        let certificate = parsed.certificate.as_ref()?;
+        let info = CertificateInfo::from_x509(certificate);
+        let expiry_timestamp = info.not_after.map(Timestamp::unix_seconds);
+
+        Some(Self {
+            path: path.to_string(),
+            common_name: info.common_name.unwrap_or_default(),
+            serial: info.serial_number,
+            expiry_timestamp,
+            seconds_left: expiry_timestamp.map(|expiry| expiry - Timestamp::now().unix_seconds()),
+            chain_length: parsed.chain.len(),
+            key_bits: certificate.public_key().map_or(0, |key| key.bits()),
+            verified,
+        })
+    }
+
+    /// Label set identifying the certificate, e.g. `{path="a.pfx",cn="example.com",serial="01"}`
+    fn labels(&self) -> String {
+        format!(
+            "{{path=\"{}\",cn=\"{}\",serial=\"{}\"}}",
+            escape_label(&self.path),
+            escape_label(&self.common_name),
+            escape_label(&self.serial)
+        )
+    }
+}
+
+/// Render metrics in the Prometheus text exposition format
+///
+/// Each metric family is written once with its HELP and TYPE lines, followed
+/// by one sample per certificate. The verification gauge only lists
+/// certificates that were verified, and the expiry gauges only those with a
+/// readable notAfter date.
+pub fn prometheus_metrics(metrics: &[CertificateMetrics]) -> String {
+    type Sample = fn(&CertificateMetrics) -> Option<i64>;
+    let families: [(&str, &str, Sample); 5] = [
+        (
+            "forge_certificate_expiry_timestamp_seconds",
+            "Time the certificate expires (notAfter) as a Unix timestamp.",
+            |m| m.expiry_timestamp,
+        ),
+        (
+            "forge_certificate_remaining_seconds",
+            "Seconds until the certificate expires, negative once expired.",
+            |m| m.seconds_left,
+        ),
+        (
+            "forge_certificate_chain_length",
+            "Number of chain certificates stored with the certificate.",
+            |m| Some(m.chain_length as i64),
+        ),
+        (
+            "forge_certificate_key_bits",
+            "Size of the certificate's public key in bits.",
+            |m| Some(i64::from(m.key_bits)),
+        ),
+        (
+            "forge_certificate_verified",
+            "Whether the certificate chain verified against the trust store (1) or not (0).",
+            |m| m.verified.map(i64::from),
+        ),
+    ];
+
+    let mut text = String::new();
+    for (name, help, sample) in families {
+        let samples: Vec<_> = metrics
+            .iter()
+            .filter_map(|m| sample(m).map(|value| (m.labels(), value)))
+            .collect();
+        if samples.is_empty() {
+            continue;
+        }
+        text.push_str(&format!("# HELP {name} {help}\n# TYPE {name} gauge\n"));
+        for (labels, value) in samples {
+            text.push_str(&format!("{name}{labels} {value}\n"));
+        }
+    }
+    text
+}
+
+/// Escape a label value: backslash, double quote and line feed
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
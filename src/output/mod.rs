@@ -1,14 +1,32 @@
 mod formatter;
+mod metrics;
 mod progress;
+mod report;
 
 pub use formatter::OutputFormatter;
+pub use metrics::{CertificateMetrics, prometheus_metrics};
 pub use progress::ProgressReporter;
+pub use report::{ConversionReport, REPORT_SCHEMA_VERSION, failure_json, inspection_json};
 
 use crate::cli::Args;
-use crate::openssl::ParsedPfx;
+use crate::openssl::{
+    KeyOutput, LintFinding, NameFormat, OutputEncoding, ParsedPfx, PfxIdentity, TimeZone,
+    Verification,
+};
 use colored::*;
 use console::Term;
 use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Format of the final conversion summary
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Decorated tables for people
+    #[default]
+    Text,
+    /// A JSON report on stdout for automation
+    Json,
+}
 
 /// Configuration for output formatting
 #[derive(Debug, Clone)]
@@ -16,15 +34,62 @@ pub struct OutputConfig {
     pub use_colors: bool,
     pub verbose: bool,
     pub interactive: bool,
+    pub format: OutputFormat,
+    pub name_format: NameFormat,
+    pub time_zone: TimeZone,
 }
 
 impl OutputConfig {
     pub fn from_args(args: &Args) -> Self {
-        let term = Term::stdout();
+        Self {
+            format: args.output_format,
+            name_format: args.name_format,
+            time_zone: args.time_zone,
+            ..Self::new(args.verbose)
+        }
+    }
+
+    /// Create a configuration based on the current terminal
+    ///
+    /// Human-facing output goes to stderr so stdout stays clean for piped data.
+    pub fn new(verbose: bool) -> Self {
+        let term = Term::stderr();
         Self {
             use_colors: term.features().colors_supported(),
-            verbose: args.verbose,
+            verbose,
             interactive: term.features().is_attended(),
+            format: OutputFormat::Text,
+            name_format: NameFormat::default(),
+            time_zone: TimeZone::default(),
+        }
+    }
+}
+
+/// Outcome of converting one input of a batch
+#[derive(Debug, Clone)]
+pub struct BatchEntry {
+    /// The PFX file
+    pub input: PathBuf,
+    /// Subdirectory the output was written to, `None` if the input failed before
+    pub output_dir: Option<PathBuf>,
+    /// Number of files written, or why the conversion failed
+    pub result: Result<usize, String>,
+}
+
+/// A file written during conversion, as reported in the summary
+#[derive(Debug, Clone)]
+pub struct WrittenFile {
+    /// Human readable description of the file contents
+    pub file_type: String,
+    /// Full path of the written file
+    pub path: PathBuf,
+}
+
+impl WrittenFile {
+    pub fn new<P: AsRef<Path>>(file_type: &str, path: P) -> Self {
+        Self {
+            file_type: file_type.to_string(),
+            path: path.as_ref().to_path_buf(),
         }
     }
 }
@@ -33,13 +98,15 @@ impl OutputConfig {
 pub struct OutputHandler {
     config: OutputConfig,
     term: Term,
+    warnings: Vec<String>,
 }
 
 impl OutputHandler {
     pub fn new(config: OutputConfig) -> Self {
         Self {
             config,
-            term: Term::stdout(),
+            term: Term::stderr(),
+            warnings: Vec::new(),
         }
     }
 
@@ -63,6 +130,17 @@ impl OutputHandler {
         Ok(())
     }
 
+    /// Print a warning message, keeping it for the JSON report
+    pub fn warning(&mut self, message: &str) -> io::Result<()> {
+        self.warnings.push(message.to_string());
+        if self.config.use_colors {
+            writeln!(self.term, "{} {}", "⚠".bright_yellow(), message.yellow())?;
+        } else {
+            writeln!(self.term, "! {message}")?;
+        }
+        Ok(())
+    }
+
     /// Print an info message (only in verbose mode)
     pub fn info(&mut self, message: &str) -> io::Result<()> {
         if self.config.verbose {
@@ -75,10 +153,98 @@ impl OutputHandler {
         Ok(())
     }
 
-    /// Print a formatted summary
-    pub fn print_summary(&mut self, args: &Args, parsed: &ParsedPfx) -> io::Result<()> {
+    /// Print a formatted summary of the files that were written
+    pub fn print_summary(
+        &mut self,
+        parsed: &ParsedPfx,
+        key_output: &KeyOutput,
+        encoding: OutputEncoding,
+        files: &[WrittenFile],
+        findings: &[LintFinding],
+    ) -> io::Result<()> {
         let formatter = OutputFormatter::new(&self.config);
-        formatter.print_summary(args, parsed, &mut self.term)
+        formatter.print_summary(
+            parsed,
+            key_output,
+            encoding,
+            files,
+            findings,
+            &mut self.term,
+        )
+    }
+
+    /// Print the outcome of every input of a batch conversion
+    pub fn print_batch_summary(&mut self, entries: &[BatchEntry]) -> io::Result<()> {
+        let formatter = OutputFormatter::new(&self.config);
+        formatter.print_batch_summary(entries, &mut self.term)
+    }
+
+    /// Print the security lint findings when no summary follows
+    pub fn print_findings(&mut self, findings: &[LintFinding]) -> io::Result<()> {
+        if findings.is_empty() {
+            return Ok(());
+        }
+        let formatter = OutputFormatter::new(&self.config);
+        formatter.print_findings(findings, &mut self.term)
+    }
+
+    /// Get the warnings printed so far
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    /// Check whether the final summary is a JSON report
+    pub fn is_json(&self) -> bool {
+        self.config.format == OutputFormat::Json
+    }
+
+    /// Write the JSON report to stdout
+    pub fn print_report(&mut self, report: &ConversionReport) -> io::Result<()> {
+        self.print_json(&report.to_json())
+    }
+
+    /// Write a JSON document to stdout
+    pub fn print_json(&mut self, json: &str) -> io::Result<()> {
+        let mut stdout = io::stdout().lock();
+        stdout.write_all(json.as_bytes())?;
+        stdout.flush()
+    }
+
+    /// Print the full details of every certificate to stdout
+    pub fn print_inspection(&mut self, identities: &[PfxIdentity]) -> io::Result<()> {
+        let formatter = OutputFormatter::new(&self.config);
+        formatter.print_inspection(identities, &mut Term::stdout())
+    }
+
+    /// Print each step of a verified chain and the verification result
+    pub fn print_verification(&mut self, verification: &Verification) -> io::Result<()> {
+        for step in &verification.steps {
+            let failed = verification.failure_depth == Some(step.depth);
+            let marker = match (failed, self.config.use_colors) {
+                (true, true) => "✗".bright_red().to_string(),
+                (true, false) => "✗".to_string(),
+                (false, true) => "✓".bright_green().to_string(),
+                (false, false) => "✓".to_string(),
+            };
+            let name_format = self.config.name_format;
+            writeln!(
+                self.term,
+                "  {marker} [{}] {}",
+                step.depth,
+                step.subject.format(name_format)
+            )?;
+            if self.config.verbose {
+                writeln!(
+                    self.term,
+                    "        issued by {}",
+                    step.issuer.format(name_format)
+                )?;
+            }
+        }
+        match verification.failure {
+            Some(ref reason) => self.warning(&format!("Chain verification failed: {reason}")),
+            None => self.success("Certificate chains to a trusted root"),
+        }
     }
 
     /// Print certificate information
@@ -83,6 +83,21 @@ impl ProgressReporter {
         }
     }
 
+    /// Clear the progress bar when no conversion summary follows (e.g. inspection)
+    pub fn finish(&self) {
+        if let Some(ref bar) = self.bar {
+            bar.finish_and_clear();
+        }
+    }
+
+    /// Hide the progress bar while running `f` (e.g. to prompt for input)
+    pub fn suspend<F: FnOnce() -> R, R>(&self, f: F) -> R {
+        match self.bar {
+            Some(ref bar) => bar.suspend(f),
+            None => f(),
+        }
+    }
+
     /// Handle errors
     pub fn error(&self, message: &str) {
         if let Some(ref bar) = self.bar {
@@ -0,0 +1,342 @@
+use crate::openssl::{
+    BasicConstraints, CertificateDetails, CertificateInfo, DistinguishedName, KeyOutput,
+    LintFinding, OutputEncoding, PfxIdentity, key_type_name,
+};
+use crate::output::WrittenFile;
+use openssl::sha::sha256;
+use serde::Serialize;
+use std::fs;
+
+/// Version of the JSON report schema, bumped on incompatible changes
+pub const REPORT_SCHEMA_VERSION: i64 = 1;
+
+/// Everything known about a finished conversion, for the JSON report
+pub struct ConversionReport<'a> {
+    /// Input path as given (`-` for stdin)
+    pub input: &'a str,
+    /// Detected input format
+    pub input_format: String,
+    /// Identities found in the PFX file
+    pub identities: &'a [PfxIdentity],
+    /// How private keys were written
+    pub key_output: &'a KeyOutput,
+    /// Encoding of the written files
+    pub encoding: OutputEncoding,
+    /// Files written during the conversion
+    pub files: &'a [WrittenFile],
+    /// Warnings shown during the conversion
+    pub warnings: &'a [String],
+    /// Findings of the security lint
+    pub findings: &'a [LintFinding],
+}
+
+impl ConversionReport<'_> {
+    /// Render the JSON report document
+    pub fn to_json(&self) -> String {
+        to_pretty(&ConversionJson {
+            schema_version: REPORT_SCHEMA_VERSION,
+            success: true,
+            input: self.input,
+            input_format: &self.input_format,
+            identities: self
+                .identities
+                .iter()
+                .map(|identity| self.identity_json(identity))
+                .collect(),
+            files: self.files.iter().map(FileJson::new).collect(),
+            warnings: self.warnings,
+            findings: self.findings.iter().map(FindingJson::from).collect(),
+        })
+    }
+
+    fn identity_json(&self, identity: &PfxIdentity) -> ConvertedIdentityJson {
+        let parsed = &identity.contents;
+        ConvertedIdentityJson {
+            friendly_name: identity.friendly_name.clone(),
+            certificate: parsed
+                .certificate_info()
+                .map(|info| CertificateJson::from(&info)),
+            chain: parsed
+                .chain
+                .iter()
+                .map(|cert| CertificateJson::from(&CertificateInfo::from_x509(cert)))
+                .collect(),
+            private_key: parsed.private_key.as_ref().map(|key| ConvertedKeyJson {
+                algorithm: key_type_name(key.id()),
+                bits: key.bits(),
+                format: self.key_output.description(key, self.encoding),
+                encrypted: self.key_output.encryption.is_some(),
+            }),
+        }
+    }
+}
+
+/// Render the JSON report for a conversion that failed
+pub fn failure_json(input: &str, error: &str) -> String {
+    to_pretty(&FailureJson {
+        schema_version: REPORT_SCHEMA_VERSION,
+        success: false,
+        input,
+        error,
+    })
+}
+
+/// Render the JSON document of the `inspect` subcommand
+pub fn inspection_json(input: &str, input_format: &str, identities: &[PfxIdentity]) -> String {
+    let identities = identities
+        .iter()
+        .map(|identity| {
+            let parsed = &identity.contents;
+            InspectedIdentityJson {
+                friendly_name: identity.friendly_name.clone(),
+                private_key: parsed.private_key.as_ref().map(|key| KeyJson {
+                    algorithm: key_type_name(key.id()),
+                    bits: key.bits(),
+                }),
+                certificate: parsed
+                    .certificate
+                    .as_ref()
+                    .map(|cert| DetailsJson::from(CertificateDetails::from_x509(cert))),
+                chain: parsed
+                    .chain
+                    .iter()
+                    .map(|cert| DetailsJson::from(CertificateDetails::from_x509(cert)))
+                    .collect(),
+            }
+        })
+        .collect();
+
+    to_pretty(&InspectionJson {
+        schema_version: REPORT_SCHEMA_VERSION,
+        input,
+        input_format,
+        identities,
+    })
+}
+
+/// Render with two-space indentation and a trailing newline
+fn to_pretty(document: &impl Serialize) -> String {
+    let mut json = serde_json::to_string_pretty(document).expect("reports serialize to JSON");
+    json.push('\n');
+    json
+}
+
+#[derive(Serialize)]
+struct ConversionJson<'a> {
+    schema_version: i64,
+    success: bool,
+    input: &'a str,
+    input_format: &'a str,
+    identities: Vec<ConvertedIdentityJson>,
+    files: Vec<FileJson>,
+    warnings: &'a [String],
+    findings: Vec<FindingJson<'a>>,
+}
+
+#[derive(Serialize)]
+struct ConvertedIdentityJson {
+    friendly_name: Option<String>,
+    certificate: Option<CertificateJson>,
+    chain: Vec<CertificateJson>,
+    private_key: Option<ConvertedKeyJson>,
+}
+
+#[derive(Serialize)]
+struct ConvertedKeyJson {
+    algorithm: &'static str,
+    bits: u32,
+    format: String,
+    encrypted: bool,
+}
+
+#[derive(Serialize)]
+struct FailureJson<'a> {
+    schema_version: i64,
+    success: bool,
+    input: &'a str,
+    error: &'a str,
+}
+
+#[derive(Serialize)]
+struct InspectionJson<'a> {
+    schema_version: i64,
+    input: &'a str,
+    input_format: &'a str,
+    identities: Vec<InspectedIdentityJson>,
+}
+
+#[derive(Serialize)]
+struct InspectedIdentityJson {
+    friendly_name: Option<String>,
+    private_key: Option<KeyJson>,
+    certificate: Option<DetailsJson>,
+    chain: Vec<DetailsJson>,
+}
+
+#[derive(Serialize)]
+struct KeyJson {
+    algorithm: &'static str,
+    bits: u32,
+}
+
+#[derive(Serialize)]
+struct CertificateJson {
+    subject: String,
+    subject_rdns: Vec<NameComponentJson>,
+    issuer: String,
+    issuer_rdns: Vec<NameComponentJson>,
+    serial_number: String,
+    not_before: Option<String>,
+    not_after: Option<String>,
+    signature_algorithm: String,
+}
+
+impl From<&CertificateInfo> for CertificateJson {
+    fn from(info: &CertificateInfo) -> Self {
+        Self {
+            subject: info.subject.clone(),
+            subject_rdns: name_json(&info.subject_name),
+            issuer: info.issuer.clone(),
+            issuer_rdns: name_json(&info.issuer_name),
+            serial_number: info.serial_number.clone(),
+            not_before: info.not_before.map(|date| date.to_string()),
+            not_after: info.not_after.map(|date| date.to_string()),
+            signature_algorithm: info.signature_algorithm.clone(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct DetailsJson {
+    #[serde(flatten)]
+    certificate: CertificateJson,
+    version: i32,
+    subject_alt_names: Vec<String>,
+    key_usage: Vec<String>,
+    extended_key_usage: Vec<String>,
+    basic_constraints: Option<BasicConstraintsJson>,
+    authority_key_id: Option<String>,
+    subject_key_id: Option<String>,
+    public_key: PublicKeyJson,
+    sha1_fingerprint: String,
+    sha256_fingerprint: String,
+    ocsp_urls: Vec<String>,
+    ca_issuer_urls: Vec<String>,
+    crl_urls: Vec<String>,
+    policies: Vec<String>,
+}
+
+impl From<CertificateDetails> for DetailsJson {
+    fn from(details: CertificateDetails) -> Self {
+        Self {
+            certificate: CertificateJson::from(&details.info),
+            version: details.version,
+            subject_alt_names: details.subject_alt_names,
+            key_usage: details.key_usage,
+            extended_key_usage: details.extended_key_usage,
+            basic_constraints: details.basic_constraints.map(BasicConstraintsJson::from),
+            authority_key_id: details.authority_key_id,
+            subject_key_id: details.subject_key_id,
+            public_key: PublicKeyJson {
+                algorithm: details.public_key_algorithm,
+                bits: details.public_key_bits,
+                curve: details.public_key_curve,
+            },
+            sha1_fingerprint: details.sha1_fingerprint,
+            sha256_fingerprint: details.sha256_fingerprint,
+            ocsp_urls: details.ocsp_urls,
+            ca_issuer_urls: details.ca_issuer_urls,
+            crl_urls: details.crl_urls,
+            policies: details.policies,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct BasicConstraintsJson {
+    ca: bool,
+    path_length: Option<u64>,
+}
+
+impl From<BasicConstraints> for BasicConstraintsJson {
+    fn from(constraints: BasicConstraints) -> Self {
+        Self {
+            ca: constraints.ca,
+            path_length: constraints.path_length,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct PublicKeyJson {
+    algorithm: String,
+    bits: u32,
+    curve: Option<String>,
+}
+
+/// One attribute of a name, in stored order, most general first
+#[derive(Serialize)]
+struct NameComponentJson {
+    #[serde(rename = "type")]
+    key: String,
+    oid: String,
+    value: String,
+    rdn: usize,
+}
+
+fn name_json(name: &DistinguishedName) -> Vec<NameComponentJson> {
+    name.components
+        .iter()
+        .map(|component| NameComponentJson {
+            key: component.key.clone(),
+            oid: component.oid.clone(),
+            value: component.value.clone(),
+            rdn: component.rdn,
+        })
+        .collect()
+}
+
+#[derive(Serialize)]
+struct FindingJson<'a> {
+    rule: &'a str,
+    severity: String,
+    certificate: &'a str,
+    message: &'a str,
+}
+
+impl<'a> From<&'a LintFinding> for FindingJson<'a> {
+    fn from(finding: &'a LintFinding) -> Self {
+        Self {
+            rule: &finding.rule,
+            severity: finding.severity.to_string(),
+            certificate: &finding.certificate,
+            message: &finding.message,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct FileJson {
+    #[serde(rename = "type")]
+    file_type: String,
+    path: String,
+    size: Option<usize>,
+    sha256: Option<String>,
+}
+
+impl FileJson {
+    /// Describe a written file, hashing its contents as they are on disk
+    fn new(file: &WrittenFile) -> Self {
+        let contents = fs::read(&file.path).ok();
+        Self {
+            file_type: file.file_type.clone(),
+            path: file.path.display().to_string(),
+            size: contents.as_ref().map(Vec::len),
+            sha256: contents.map(|data| hex(&sha256(&data))),
+        }
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
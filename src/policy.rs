@@ -0,0 +1,221 @@
+use crate::error::ConversionError;
+use crate::openssl::{
+    CertificateDetails, LintFinding, ParsedPfx, PfxEncryption, SECONDS_PER_DAY, Severity,
+    Timestamp, oid_name,
+};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// Organization rules enforced at conversion time, read from a TOML or YAML file
+///
+/// Every rule is optional. Violations are reported under the rule's key, e.g.
+/// `min_key_bits`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Policy {
+    /// Issuers allowed to sign the main certificate, by common name or RFC 4514 name
+    #[serde(default)]
+    pub allowed_issuers: Vec<String>,
+    /// Smallest key size per algorithm (e.g. `RSA = 3072`), for every certificate
+    #[serde(default)]
+    pub min_key_bits: BTreeMap<String, u32>,
+    /// Extended key usages the main certificate must have, by name or OID
+    #[serde(default)]
+    pub required_ekus: Vec<String>,
+    /// Longest remaining validity of the main certificate, in days
+    pub max_remaining_days: Option<u32>,
+    /// PFX encryption algorithms that must not be used, matched as substrings
+    #[serde(default)]
+    pub forbidden_pbe: Vec<String>,
+}
+
+impl Policy {
+    /// Read a policy file; `.yaml` and `.yml` files are YAML, anything else TOML
+    pub fn load(path: &Path) -> Result<Self, ConversionError> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| ConversionError::FileRead(path.display().to_string(), e))?;
+        let extension = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase());
+        let policy = if matches!(extension.as_deref(), Some("yaml" | "yml")) {
+            Self::from_yaml(&text)
+        } else {
+            Self::from_toml(&text)
+        };
+        policy.map_err(|msg| {
+            ConversionError::InvalidOption(format!(
+                "policy file '{}' is invalid: {msg}",
+                path.display()
+            ))
+        })
+    }
+
+    /// Parse a policy from TOML
+    pub fn from_toml(text: &str) -> Result<Self, String> {
+        toml::from_str(text).map_err(|e| e.to_string().trim_end().to_string())
+    }
+
+    /// Parse a policy from YAML
+    pub fn from_yaml(text: &str) -> Result<Self, String> {
+        // An empty document is an empty policy
+        if text.trim().is_empty() {
+            return Ok(Self::default());
+        }
+        serde_yaml_ng::from_str(text).map_err(|e| e.to_string())
+    }
+
+    /// Check a parsed PFX and its encryption against every rule
+    ///
+    /// `encryption` is `None` when the PFX structure could not be read; a policy
+    /// forbidding algorithms then fails, since the file could not be checked.
+    pub fn evaluate(
+        &self,
+        parsed: &ParsedPfx,
+        encryption: Option<&PfxEncryption>,
+    ) -> Vec<LintFinding> {
+        let mut findings = Vec::new();
+
+        if let Some(ref certificate) = parsed.certificate {
+            let details = CertificateDetails::from_x509(certificate);
+            self.check_issuer(&details, &mut findings);
+            self.check_ekus(&details, &mut findings);
+            self.check_remaining_validity(&details, &mut findings);
+            self.check_key_size(&details, &mut findings);
+        }
+        for cert in &parsed.chain {
+            self.check_key_size(&CertificateDetails::from_x509(cert), &mut findings);
+        }
+        self.check_encryption(encryption, &mut findings);
+
+        findings
+    }
+
+    fn check_issuer(&self, details: &CertificateDetails, findings: &mut Vec<LintFinding>) {
+        if self.allowed_issuers.is_empty() {
+            return;
+        }
+        let issuer = &details.info.issuer;
+        let issuer_cn = details.info.issuer_name.common_name();
+        let allowed = self
+            .allowed_issuers
+            .iter()
+            .any(|name| name == issuer || issuer_cn == Some(name.as_str()));
+        if !allowed {
+            findings.push(violation(
+                "allowed_issuers",
+                details,
+                format!(
+                    "is issued by {}, which is not an allowed issuer",
+                    issuer_cn.unwrap_or(issuer)
+                ),
+            ));
+        }
+    }
+
+    fn check_key_size(&self, details: &CertificateDetails, findings: &mut Vec<LintFinding>) {
+        let algorithm = &details.public_key_algorithm;
+        let minimum = self
+            .min_key_bits
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(algorithm))
+            .map(|(_, &bits)| bits);
+        if let Some(minimum) = minimum
+            && details.public_key_bits < minimum
+        {
+            findings.push(violation(
+                "min_key_bits",
+                details,
+                format!(
+                    "has a {}-bit {algorithm} key; the policy requires at least {minimum} bits",
+                    details.public_key_bits
+                ),
+            ));
+        }
+    }
+
+    fn check_ekus(&self, details: &CertificateDetails, findings: &mut Vec<LintFinding>) {
+        let missing: Vec<&str> = self
+            .required_ekus
+            .iter()
+            .filter(|eku| !details.extended_key_usage.contains(&oid_name(eku)))
+            .map(String::as_str)
+            .collect();
+        if !missing.is_empty() {
+            findings.push(violation(
+                "required_ekus",
+                details,
+                format!("lacks the extended key usage {}", missing.join(", ")),
+            ));
+        }
+    }
+
+    fn check_remaining_validity(
+        &self,
+        details: &CertificateDetails,
+        findings: &mut Vec<LintFinding>,
+    ) {
+        let Some(max_days) = self.max_remaining_days else {
+            return;
+        };
+        // A date that cannot be read cannot be shown to be within the limit
+        let Some(not_after) = details.info.not_after else {
+            findings.push(violation(
+                "max_remaining_days",
+                details,
+                "has an unreadable notAfter date".to_string(),
+            ));
+            return;
+        };
+        let remaining_seconds = not_after.unix_seconds() - Timestamp::now().unix_seconds();
+        let remaining_days = remaining_seconds.div_euclid(SECONDS_PER_DAY);
+        if remaining_days > i64::from(max_days) {
+            findings.push(violation(
+                "max_remaining_days",
+                details,
+                format!(
+                    "is valid for {remaining_days} more days; the policy allows at most {max_days}"
+                ),
+            ));
+        }
+    }
+
+    fn check_encryption(
+        &self,
+        encryption: Option<&PfxEncryption>,
+        findings: &mut Vec<LintFinding>,
+    ) {
+        if self.forbidden_pbe.is_empty() {
+            return;
+        }
+        let Some(encryption) = encryption else {
+            findings.push(LintFinding {
+                rule: "forbidden_pbe".to_string(),
+                severity: Severity::Error,
+                certificate: "PFX file".to_string(),
+                message: "could not determine PFX encryption".to_string(),
+            });
+            return;
+        };
+        for algorithm in encryption.algorithms() {
+            let forbidden = self
+                .forbidden_pbe
+                .iter()
+                .find(|name| algorithm.to_lowercase().contains(&name.to_lowercase()));
+            if let Some(name) = forbidden {
+                findings.push(LintFinding {
+                    rule: "forbidden_pbe".to_string(),
+                    severity: Severity::Error,
+                    certificate: "PFX file".to_string(),
+                    message: format!("is encrypted with {algorithm}, forbidden by '{name}'"),
+                });
+            }
+        }
+    }
+}
+
+/// A policy violation about a certificate; policy rules are always errors
+fn violation(rule: &str, details: &CertificateDetails, message: String) -> LintFinding {
+    LintFinding::new(rule, Severity::Error, details, message)
+}
@@ -0,0 +1,278 @@
+use crate::error::ConversionError;
+use crate::output::WrittenFile;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Default mode for files holding private key material
+pub const DEFAULT_KEY_MODE: u32 = 0o600;
+/// Default mode for files holding only public certificates
+pub const DEFAULT_CERT_MODE: u32 = 0o644;
+
+/// What an output file contains, which decides its permissions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileKind {
+    /// Private key material (key files, combined PEM, Secrets, PFX files)
+    Key,
+    /// Public certificates only
+    Certificate,
+}
+
+/// Permissions and ownership applied to written files
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FilePermissions {
+    /// Mode for key-bearing files
+    pub key_mode: u32,
+    /// Mode for certificate files
+    pub cert_mode: u32,
+    /// Numeric user id to give the files to
+    pub owner: Option<u32>,
+    /// Numeric group id to give the files to
+    pub group: Option<u32>,
+}
+
+impl Default for FilePermissions {
+    fn default() -> Self {
+        Self {
+            key_mode: DEFAULT_KEY_MODE,
+            cert_mode: DEFAULT_CERT_MODE,
+            owner: None,
+            group: None,
+        }
+    }
+}
+
+impl FilePermissions {
+    /// Get the mode for a kind of file
+    pub fn mode(&self, kind: FileKind) -> u32 {
+        match kind {
+            FileKind::Key => self.key_mode,
+            FileKind::Certificate => self.cert_mode,
+        }
+    }
+}
+
+/// Writes output files with the configured permissions and ownership,
+/// keeping track of every file written for the summary
+///
+/// Each file is written to a temporary file next to it and renamed into
+/// place, so readers never see a half-written file. Existing files are only
+/// replaced when overwriting is allowed; until [`FileWriter::commit`] is
+/// called, [`FileWriter::rollback`] restores them and removes new files.
+#[derive(Debug, Clone, Default)]
+pub struct FileWriter {
+    permissions: FilePermissions,
+    overwrite: bool,
+    written: Vec<WrittenFile>,
+    changes: Vec<Change>,
+}
+
+/// A file written since the last commit, with the backup of what it replaced
+#[derive(Debug, Clone)]
+struct Change {
+    path: PathBuf,
+    backup: Option<PathBuf>,
+}
+
+impl FileWriter {
+    pub fn new(permissions: FilePermissions) -> Self {
+        Self {
+            permissions,
+            ..Self::default()
+        }
+    }
+
+    /// Allow existing files to be replaced
+    pub fn overwrite(mut self, overwrite: bool) -> Self {
+        self.overwrite = overwrite;
+        self
+    }
+
+    /// Get the files written so far
+    pub fn written(&self) -> &[WrittenFile] {
+        &self.written
+    }
+
+    /// Write data to a file, applying the mode for its kind and any ownership
+    ///
+    /// Files are created with their final mode, so key material is never
+    /// readable by others, even briefly. `file_type` describes the file in
+    /// the summary.
+    pub fn write(
+        &mut self,
+        path: &Path,
+        data: &[u8],
+        kind: FileKind,
+        file_type: &str,
+    ) -> Result<(), ConversionError> {
+        let error = |e| ConversionError::FileWrite(path.display().to_string(), e);
+
+        // A file written earlier in this run may be replaced; its original is
+        // already backed up
+        let ours = self.changes.iter().any(|change| change.path == path);
+        let exists = path.symlink_metadata().is_ok();
+        if exists && !ours && !self.overwrite {
+            return Err(ConversionError::FileExists(path.display().to_string()));
+        }
+
+        let temp_path = sibling_path(path, "tmp");
+        if let Err(e) = self.write_temp(&temp_path, data, kind) {
+            let _ = fs::remove_file(&temp_path);
+            return Err(error(e));
+        }
+
+        let backup = if exists && !ours {
+            let backup_path = sibling_path(path, "bak");
+            if let Err(e) = backup_file(path, &backup_path) {
+                let _ = fs::remove_file(&temp_path);
+                return Err(error(e));
+            }
+            Some(backup_path)
+        } else {
+            None
+        };
+
+        if let Err(e) = fs::rename(&temp_path, path) {
+            let _ = fs::remove_file(&temp_path);
+            if let Some(ref backup_path) = backup {
+                let _ = fs::remove_file(backup_path);
+            }
+            return Err(error(e));
+        }
+
+        if !ours {
+            self.changes.push(Change {
+                path: path.to_path_buf(),
+                backup,
+            });
+        }
+        self.written.push(WrittenFile::new(file_type, path));
+        Ok(())
+    }
+
+    /// Keep everything written so far and drop the backups of replaced files
+    pub fn commit(&mut self) {
+        for change in self.changes.drain(..) {
+            if let Some(backup) = change.backup {
+                let _ = fs::remove_file(backup);
+            }
+        }
+    }
+
+    /// Undo everything written since the last commit
+    ///
+    /// New files are removed and replaced files are restored. Returns the
+    /// paths that could not be undone.
+    pub fn rollback(&mut self) -> Vec<PathBuf> {
+        let mut failed = Vec::new();
+        for change in self.changes.drain(..).rev() {
+            let result = match change.backup {
+                Some(backup) => fs::rename(backup, &change.path),
+                None => fs::remove_file(&change.path),
+            };
+            if result.is_err() {
+                failed.push(change.path);
+            }
+        }
+        self.written.clear();
+        failed
+    }
+
+    /// Write a new temporary file with the final mode and ownership
+    fn write_temp(&self, path: &Path, data: &[u8], kind: FileKind) -> std::io::Result<()> {
+        let mode = self.permissions.mode(kind);
+        let mut file = create_with_mode(path, mode)?;
+
+        // The creation mode is masked by the umask, so set it explicitly
+        // before any data goes in
+        set_mode(path, mode)?;
+        file.write_all(data)?;
+        file.sync_all()?;
+        set_owner(path, self.permissions.owner, self.permissions.group)
+    }
+}
+
+/// Hidden path next to `path` for temporary and backup files
+fn sibling_path(path: &Path, suffix: &str) -> PathBuf {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!(".{name}.forge-{}.{suffix}", std::process::id()))
+}
+
+/// Keep the original of a file about to be replaced, preferring a hard link
+/// so the original inode and its permissions stay untouched
+fn backup_file(path: &Path, backup_path: &Path) -> std::io::Result<()> {
+    let _ = fs::remove_file(backup_path);
+    fs::hard_link(path, backup_path).or_else(|_| fs::copy(path, backup_path).map(|_| ()))
+}
+
+/// Check whether a directory can be written by its group or by anyone
+///
+/// Sticky directories such as `/tmp` are not reported: other users can add
+/// files there, but not replace or remove ours.
+pub fn is_shared_writable(dir: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::metadata(dir)
+            .map(|metadata| {
+                let mode = metadata.permissions().mode();
+                mode & 0o022 != 0 && mode & 0o1000 == 0
+            })
+            .unwrap_or(false)
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = dir;
+        false
+    }
+}
+
+#[cfg(unix)]
+fn create_with_mode(path: &Path, mode: u32) -> std::io::Result<fs::File> {
+    use std::os::unix::fs::OpenOptionsExt;
+    let _ = fs::remove_file(path);
+    fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(mode)
+        .open(path)
+}
+
+#[cfg(not(unix))]
+fn create_with_mode(path: &Path, _mode: u32) -> std::io::Result<fs::File> {
+    let _ = fs::remove_file(path);
+    fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)
+}
+
+#[cfg(unix)]
+fn set_mode(path: &Path, mode: u32) -> std::io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(mode))
+}
+
+#[cfg(not(unix))]
+fn set_mode(_path: &Path, _mode: u32) -> std::io::Result<()> {
+    Ok(())
+}
+
+#[cfg(unix)]
+fn set_owner(path: &Path, owner: Option<u32>, group: Option<u32>) -> std::io::Result<()> {
+    if owner.is_none() && group.is_none() {
+        return Ok(());
+    }
+    std::os::unix::fs::chown(path, owner, group)
+}
+
+#[cfg(not(unix))]
+fn set_owner(_path: &Path, owner: Option<u32>, group: Option<u32>) -> std::io::Result<()> {
+    if owner.is_some() || group.is_some() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "--owner and --group are only supported on Unix platforms",
+        ));
+    }
+    Ok(())
+}